@@ -0,0 +1,52 @@
+use crate::error::WeatherError;
+use crate::http;
+use crate::model::Alert;
+use crate::owm;
+use crate::time::{self, TimeFormat};
+use serde_json::Value;
+
+/// Fetches active weather alerts from the One Call API, which requires a
+/// "One Call by Call" subscription on the key
+pub fn get_alerts(api_key: &str, lat: &f64, lon: &f64, lang: &str) -> Result<Value, WeatherError> {
+    let (lat, lon) = (lat.to_string(), lon.to_string());
+    let url = http::with_query(
+        &owm::url("/data/3.0/onecall"),
+        &[("lat", &lat), ("lon", &lon), ("exclude", "current,minutely,hourly,daily"), ("appid", api_key), ("lang", lang)],
+    );
+
+    let response = http::get(&url).map_err(|e| match WeatherError::from(e) {
+        WeatherError::Auth(message) => {
+            WeatherError::Auth(format!("{}; alerts need a One Call API 3.0 subscription", message))
+        }
+        error => error,
+    })?;
+    Ok(serde_json::from_str(&response.into_string()?)?)
+}
+
+/// Print each alert with its validity period, in the location's local time
+pub fn print_alerts(alerts: &[Alert], utc_offset: i64, time_format: TimeFormat) {
+    if alerts.is_empty() {
+        println!("No active weather alerts.");
+        return;
+    }
+
+    let local = |timestamp| {
+        format!(
+            "{} {}",
+            time::weekday(timestamp, utc_offset),
+            time::format_time(timestamp, utc_offset, time_format)
+        )
+    };
+    for (i, alert) in alerts.iter().enumerate() {
+        if i > 0 {
+            println!();
+        }
+        println!("! {} ({} - {})", alert.event, local(alert.start), local(alert.end));
+        if !alert.sender.is_empty() {
+            println!("  Issued by {}", alert.sender);
+        }
+        for line in alert.description.lines().filter(|line| !line.trim().is_empty()) {
+            println!("  {}", line.trim());
+        }
+    }
+}
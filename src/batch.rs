@@ -0,0 +1,56 @@
+use std::fs;
+use std::io::{self, Read};
+use std::panic;
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+
+/// Locations fetched at the same time. API calls still wait their turn
+/// under the per-minute limit.
+const WORKERS: usize = 4;
+
+/// Reads the locations of a batch file, or of stdin for "-", one per line.
+/// Blank lines and lines starting with '#' are skipped.
+pub fn read_locations(source: &Path) -> io::Result<Vec<String>> {
+    let contents = match source.to_str() {
+        Some("-") => {
+            let mut contents = String::new();
+            io::stdin().read_to_string(&mut contents)?;
+            contents
+        }
+        _ => fs::read_to_string(source)?,
+    };
+    Ok(contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(str::to_string)
+        .collect())
+}
+
+/// Runs `fetch` for every item, a few at a time, returning the results in
+/// the items' order
+pub fn fetch_all<T: Sync, R: Send>(items: &[T], fetch: impl Fn(&T) -> R + Sync) -> Vec<R> {
+    let next = AtomicUsize::new(0);
+    let results: Mutex<Vec<Option<R>>> = Mutex::new(items.iter().map(|_| None).collect());
+    thread::scope(|scope| {
+        let handles: Vec<_> = (0..WORKERS.min(items.len()))
+            .map(|_| {
+                scope.spawn(|| loop {
+                    let index = next.fetch_add(1, Ordering::Relaxed);
+                    let Some(item) = items.get(index) else {
+                        break;
+                    };
+                    let result = fetch(item);
+                    results.lock().unwrap_or_else(|e| e.into_inner())[index] = Some(result);
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.join().unwrap_or_else(|panic| panic::resume_unwind(panic));
+        }
+    });
+    // Every slot is filled once the workers are done
+    results.into_inner().unwrap_or_else(|e| e.into_inner()).into_iter().flatten().collect()
+}
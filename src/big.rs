@@ -0,0 +1,57 @@
+use crate::current;
+use crate::model::CurrentWeather;
+use crate::units::Units;
+
+/// Glyphs as 3x5 pixel bitmaps, each pixel drawn two cells wide so the
+/// figures come out roughly square
+const GLYPHS: [(char, [&str; 5]); 16] = [
+    ('0', ["###", "# #", "# #", "# #", "###"]),
+    ('1', [" # ", "## ", " # ", " # ", "###"]),
+    ('2', ["###", "  #", "###", "#  ", "###"]),
+    ('3', ["###", "  #", "###", "  #", "###"]),
+    ('4', ["# #", "# #", "###", "  #", "  #"]),
+    ('5', ["###", "#  ", "###", "  #", "###"]),
+    ('6', ["###", "#  ", "###", "# #", "###"]),
+    ('7', ["###", "  #", "  #", "  #", "  #"]),
+    ('8', ["###", "# #", "###", "# #", "###"]),
+    ('9', ["###", "# #", "###", "  #", "###"]),
+    ('-', ["   ", "   ", "###", "   ", "   "]),
+    ('°', ["###", "# #", "###", "   ", "   "]),
+    ('C', ["###", "#  ", "#  ", "#  ", "###"]),
+    ('F', ["###", "#  ", "###", "#  ", "#  "]),
+    ('K', ["# #", "# #", "## ", "# #", "# #"]),
+    (' ', ["   ", "   ", "   ", "   ", "   "]),
+];
+
+/// Renders text in block figures, five rows high. Characters without a
+/// glyph are left out.
+pub fn render(text: &str) -> [String; 5] {
+    let mut rows: [String; 5] = Default::default();
+    let glyphs = text.chars().filter_map(|c| GLYPHS.iter().find(|(glyph, _)| *glyph == c));
+    for (i, (_, bitmap)) in glyphs.enumerate() {
+        for (row, pixels) in rows.iter_mut().zip(bitmap) {
+            if i > 0 {
+                row.push_str("  ");
+            }
+            row.extend(pixels.chars().map(|pixel| if pixel == '#' { "██" } else { "  " }));
+        }
+    }
+    rows
+}
+
+/// Prints the temperature in block figures with the condition art beside
+/// it, and the city and conditions underneath, to read from across a room
+pub fn print_big(weather: &CurrentWeather, units: &Units) {
+    let temperature = format!("{:.0}{}", units.temperature(weather.temp), units.temperature_symbol());
+    let figures = render(&temperature.replace("-0", "0"));
+    let art = current::condition_art(&weather.condition);
+
+    for (i, figure) in figures.iter().enumerate() {
+        // Lines the art up with the middle rows of the figures
+        let art_row = i.checked_sub(1).and_then(|row| art.get(row)).copied().unwrap_or("");
+        let line = format!("{}    {}", figure, art_row);
+        println!("{}", line.trim_end());
+    }
+    println!();
+    println!("{}, {}", weather.city, weather.description);
+}
@@ -0,0 +1,62 @@
+use directories::ProjectDirs;
+use serde_json::Value;
+use std::error::Error;
+use std::fs;
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// Cache key for a location, with coordinates rounded to about 100 m so
+/// small GPS jitter still finds the same entry
+pub fn coords_key(lat: f64, lon: f64) -> String {
+    format!("{:.3},{:.3}", lat, lon)
+}
+
+/// Saves the latest current weather response for a location, to show when
+/// the network isn't available
+pub fn save_current(key: &str, json: &Value) -> Result<(), Box<dyn Error>> {
+    let path = current_path(key).ok_or("no data directory")?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(path, serde_json::to_string(json)?)?;
+    Ok(())
+}
+
+/// The last current weather response saved for a location
+pub fn load_current(key: &str) -> Option<Value> {
+    let contents = fs::read_to_string(current_path(key)?).ok()?;
+    serde_json::from_str(&contents).ok()
+}
+
+/// How long ago the weather for a location was saved
+pub fn current_age(key: &str) -> Option<Duration> {
+    let modified = fs::metadata(current_path(key)?).ok()?.modified().ok()?;
+    Some(modified.elapsed().unwrap_or_default())
+}
+
+/// Notes that a refresh of a location is starting, unless one started less
+/// than `retry` ago. Returns whether the caller should go ahead.
+pub fn claim_refresh(key: &str, retry: Duration) -> bool {
+    let Some(path) = current_path(key).map(|path| path.with_extension("refresh")) else {
+        return false;
+    };
+    let recent = fs::metadata(&path)
+        .and_then(|metadata| metadata.modified())
+        .is_ok_and(|modified| modified.elapsed().unwrap_or_default() < retry);
+    if recent {
+        return false;
+    }
+    path.parent().is_some_and(|dir| fs::create_dir_all(dir).is_ok()) && fs::write(&path, "").is_ok()
+}
+
+fn current_path(key: &str) -> Option<PathBuf> {
+    let name: String = key
+        .chars()
+        .map(|c| match c {
+            '-' => 'm',
+            c if c.is_ascii_alphanumeric() => c,
+            _ => '_',
+        })
+        .collect();
+    ProjectDirs::from("", "", "rusty_weather").map(|dirs| dirs.data_dir().join("cache").join(format!("current-{}.json", name)))
+}
@@ -0,0 +1,104 @@
+use serde::Serialize;
+
+/// Where a feels-like temperature comes from
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FeelsLikeSource {
+    /// Given by the weather provider
+    Reported,
+    /// Computed, for hot and humid weather
+    HeatIndex,
+    /// Computed, for cold and windy weather
+    WindChill,
+    /// Neither formula applies, so it's the air temperature
+    Air,
+}
+
+/// Apparent temperature in °C for providers that don't report one: the
+/// heat index when hot and humid, the wind chill when cold and windy, and
+/// otherwise the air temperature.
+pub fn feels_like(celsius: f64, humidity: f64, wind_mps: f64) -> (f64, FeelsLikeSource) {
+    if let Some(index) = heat_index(celsius, humidity) {
+        return (index, FeelsLikeSource::HeatIndex);
+    }
+    if let Some(chill) = wind_chill(celsius, wind_mps) {
+        return (chill, FeelsLikeSource::WindChill);
+    }
+    (celsius, FeelsLikeSource::Air)
+}
+
+/// NWS heat index in °C, from 80°F (26.7°C) up; None below that
+pub fn heat_index(celsius: f64, humidity: f64) -> Option<f64> {
+    let (t, rh) = (celsius * 9.0 / 5.0 + 32.0, humidity);
+    // Steadman's simple formula, which the NWS uses below 80°F
+    let simple = 0.5 * (t + 61.0 + (t - 68.0) * 1.2 + rh * 0.094);
+    if (simple + t) / 2.0 < 80.0 {
+        return None;
+    }
+
+    // Rothfusz regression, with the NWS adjustments for very dry and very humid air
+    let mut index = -42.379 + 2.049_015_23 * t + 10.143_331_27 * rh
+        - 0.224_755_41 * t * rh
+        - 0.006_837_83 * t * t
+        - 0.054_817_17 * rh * rh
+        + 0.001_228_74 * t * t * rh
+        + 0.000_852_82 * t * rh * rh
+        - 0.000_001_99 * t * t * rh * rh;
+    if rh < 13.0 && (80.0..=112.0).contains(&t) {
+        index -= (13.0 - rh) / 4.0 * ((17.0 - (t - 95.0).abs()) / 17.0).sqrt();
+    } else if rh > 85.0 && (80.0..=87.0).contains(&t) {
+        index += (rh - 85.0) / 10.0 * (87.0 - t) / 5.0;
+    }
+    Some((index - 32.0) * 5.0 / 9.0)
+}
+
+/// NWS wind chill in °C, defined at 10°C (50°F) and below with wind over
+/// 4.8 km/h (3 mph); None otherwise
+pub fn wind_chill(celsius: f64, wind_mps: f64) -> Option<f64> {
+    let kph = wind_mps * 3.6;
+    if celsius > 10.0 || kph <= 4.8 {
+        return None;
+    }
+    let v = kph.powf(0.16);
+    Some(13.12 + 0.6215 * celsius - 11.37 * v + 0.3965 * celsius * v)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn heat_index_matches_the_nws_table() {
+        // 90°F at 60% is 100°F in the NWS heat index table
+        let index = heat_index(32.22, 60.0).unwrap();
+        assert!((index - 37.8).abs() < 0.5, "{}", index);
+        // 100°F at 40% is 109°F
+        let index = heat_index(37.78, 40.0).unwrap();
+        assert!((index - 42.8).abs() < 0.5, "{}", index);
+        assert_eq!(heat_index(20.0, 90.0), None);
+    }
+
+    #[test]
+    fn heat_index_adjusts_for_dry_and_humid_air() {
+        // 100°F in very dry air feels cooler than it is
+        assert!(heat_index(37.78, 10.0).unwrap() < 37.78);
+        // 84°F in very humid air feels hotter
+        assert!(heat_index(29.0, 95.0).unwrap() > 29.0);
+    }
+
+    #[test]
+    fn wind_chill_matches_the_nws_table() {
+        // 0°F with 15 mph of wind feels like -19°F
+        let chill = wind_chill(-17.78, 6.706).unwrap();
+        assert!((chill - -28.3).abs() < 0.5, "{}", chill);
+        assert_eq!(wind_chill(10.5, 10.0), None);
+        assert_eq!(wind_chill(-5.0, 1.0), None);
+    }
+
+    #[test]
+    fn feels_like_falls_back_to_the_air() {
+        assert_eq!(feels_like(18.0, 50.0, 3.0), (18.0, FeelsLikeSource::Air));
+        assert_eq!(feels_like(-5.0, 50.0, 5.0).1, FeelsLikeSource::WindChill);
+        assert_eq!(feels_like(35.0, 50.0, 5.0).1, FeelsLikeSource::HeatIndex);
+    }
+}
@@ -0,0 +1,161 @@
+use crate::error::WeatherError;
+use crate::model::CurrentWeather;
+use crate::units::Units;
+use crate::wind;
+use std::io::{self, IsTerminal};
+
+/// Spreads beyond which providers are said to disagree, in °C, mm and m/s
+pub const TEMP_SPREAD: f64 = 2.0;
+pub const PRECIPITATION_SPREAD: f64 = 0.5;
+pub const WIND_SPREAD: f64 = 3.0;
+
+/// What one provider said, or why it couldn't say
+pub struct Reading<'a> {
+    pub provider: &'a str,
+    pub weather: Result<CurrentWeather, WeatherError>,
+}
+
+/// A provider's name and the value it reported
+type Value<'a> = (&'a str, f64);
+
+/// A compared measurement, where readings that agree within `spread` of
+/// each other count as the same
+struct Measure {
+    name: &'static str,
+    spread: f64,
+    value: fn(&CurrentWeather) -> Option<f64>,
+    format: fn(&Units, f64) -> String,
+}
+
+const MEASURES: [Measure; 3] = [
+    Measure {
+        name: "Temperature",
+        spread: TEMP_SPREAD,
+        value: |weather| Some(weather.temp),
+        format: |units, value| units.format_temperature(value),
+    },
+    Measure {
+        name: "Precipitation",
+        spread: PRECIPITATION_SPREAD,
+        value: |weather| weather.precipitation,
+        format: |units, value| units.format_precipitation(value),
+    },
+    Measure {
+        name: "Wind",
+        spread: WIND_SPREAD,
+        value: |weather| Some(weather.wind.speed),
+        format: |units, value| units.format_wind(value),
+    },
+];
+
+impl Measure {
+    /// Middle of the readings that have a value, when there are at least two
+    fn median(&self, readings: &[Reading]) -> Option<f64> {
+        let mut values: Vec<f64> = readings.iter().filter_map(|r| r.weather.as_ref().ok().and_then(self.value)).collect();
+        if values.len() < 2 {
+            return None;
+        }
+        values.sort_by(f64::total_cmp);
+        let mid = values.len() / 2;
+        Some(match values.len() % 2 {
+            0 => (values[mid - 1] + values[mid]) / 2.0,
+            _ => values[mid],
+        })
+    }
+
+    /// Whether a reading is far enough from the median to stand out
+    fn is_outlier(&self, weather: &CurrentWeather, median: Option<f64>) -> bool {
+        match ((self.value)(weather), median) {
+            (Some(value), Some(median)) => (value - median).abs() > self.spread / 2.0,
+            _ => false,
+        }
+    }
+
+    /// The lowest and highest reading, when they're further apart than `spread`
+    fn disagreement<'a>(&self, readings: &[Reading<'a>]) -> Option<(Value<'a>, Value<'a>)> {
+        let values: Vec<Value> = readings
+            .iter()
+            .filter_map(|r| Some((r.provider, r.weather.as_ref().ok().and_then(self.value)?)))
+            .collect();
+        let low = values.iter().copied().min_by(|a, b| a.1.total_cmp(&b.1))?;
+        let high = values.iter().copied().max_by(|a, b| a.1.total_cmp(&b.1))?;
+        (high.1 - low.1 > self.spread).then_some((low, high))
+    }
+}
+
+/// Prints each provider's temperature, precipitation and wind side by side,
+/// marking readings that stand out from the rest, then says where they
+/// disagree
+pub fn print_comparison(readings: &[Reading], units: &Units, plain: bool) {
+    let medians: Vec<Option<f64>> = MEASURES.iter().map(|measure| measure.median(readings)).collect();
+    let color = io::stdout().is_terminal() && !plain;
+    let cell = |measure: &Measure, median: Option<f64>, weather: &CurrentWeather| {
+        let text = (measure.value)(weather).map_or("-".to_string(), |value| (measure.format)(units, value));
+        let text = match measure.name {
+            "Wind" => format!("{} {}", text, wind::compass(weather.wind.deg)),
+            _ => text,
+        };
+        (text, measure.is_outlier(weather, median))
+    };
+
+    if plain {
+        for reading in readings {
+            match &reading.weather {
+                Ok(weather) => {
+                    let values: Vec<String> = MEASURES
+                        .iter()
+                        .zip(&medians)
+                        .map(|(measure, median)| format!("{} {}", measure.name.to_lowercase(), cell(measure, *median, weather).0))
+                        .collect();
+                    println!("{}: {}, {}", reading.provider, values.join(", "), weather.description);
+                }
+                Err(e) => println!("{}: error: {}", reading.provider, e),
+            }
+        }
+    } else {
+        let name_width = readings.iter().map(|r| r.provider.chars().count()).max().unwrap_or(0).max("Provider".len());
+        println!("{:name_width$}  {:10}  {:10}  {:14}  Conditions", "Provider", "Temp", "Precip", "Wind");
+        for reading in readings {
+            let weather = match &reading.weather {
+                Ok(weather) => weather,
+                Err(e) => {
+                    println!("{:name_width$}  error: {}", reading.provider, e);
+                    continue;
+                }
+            };
+            let mut line = format!("{:name_width$}", reading.provider);
+            for ((measure, median), width) in MEASURES.iter().zip(&medians).zip([10, 10, 14]) {
+                let (text, outlier) = cell(measure, *median, weather);
+                // Padding goes inside the highlight so the columns stay aligned
+                let padded = format!("{:width$}", text);
+                line.push_str("  ");
+                line.push_str(&match (outlier, color) {
+                    (true, true) => format!("\x1b[7m{}\x1b[0m", padded),
+                    (true, false) => format!("{:width$}", format!("{}*", text)),
+                    (false, _) => padded,
+                });
+            }
+            println!("{}  {}", line, weather.description);
+        }
+    }
+
+    let disagreements: Vec<String> = MEASURES
+        .iter()
+        .filter_map(|measure| {
+            let ((low_name, low), (high_name, high)) = measure.disagreement(readings)?;
+            let difference = match measure.name {
+                // Differences have no offset, unlike temperatures
+                "Temperature" => format!("{:.1}{}", units.temperature(high) - units.temperature(low), units.temperature_symbol()),
+                _ => (measure.format)(units, high - low),
+            };
+            Some(format!("{} differs by {} ({} lowest, {} highest)", measure.name, difference, low_name, high_name))
+        })
+        .collect();
+    println!();
+    let answered = readings.iter().filter(|r| r.weather.is_ok()).count();
+    match (answered, disagreements.is_empty()) {
+        (0 | 1, _) => println!("Only {} provider answered, so there's nothing to compare.", answered),
+        (_, true) => println!("The providers agree."),
+        (_, false) => disagreements.iter().for_each(|line| println!("{}", line)),
+    }
+}
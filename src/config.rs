@@ -0,0 +1,775 @@
+use crate::current::{CardOptions, Field, DEFAULT_FIELDS};
+use crate::error::WeatherError;
+use crate::export::Influx;
+use crate::http::HttpSettings;
+use crate::i18n;
+use crate::location;
+use crate::provider::{self, Plugin, Provider};
+use crate::quota::Limits;
+use crate::secret;
+use crate::station::{Source as StationSource, Station};
+use crate::time::TimeFormat;
+use crate::units::{PRESETS, PrecipitationUnit, PressureUnit, SpeedUnit, TemperatureUnit, Units};
+use directories::ProjectDirs;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fs::{self, File, OpenOptions};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Fields holding lists, given as comma-separated items on the command line
+/// and in environment variables
+const LIST_FIELDS: [&str; 1] = ["fields"];
+
+/// Prefix for environment variables overriding config fields
+const ENV_PREFIX: &str = "RUSTY_WEATHER_";
+
+/// Config file name, also the location older versions used in the working directory
+const CONFIG_FILE: &str = "config.yaml";
+
+/// Config file name when using TOML
+const TOML_CONFIG_FILE: &str = "config.toml";
+
+/// Version of the config format this build reads and writes. Renaming,
+/// moving or reinterpreting a field bumps it, with an entry in `MIGRATIONS`
+/// that rewrites older files; new fields with defaults don't need one.
+pub const CONFIG_VERSION: u32 = 1;
+
+/// Upgrades a config to the next version, the first one from version 0
+const MIGRATIONS: [fn(&mut Map<String, Value>); CONFIG_VERSION as usize] = [
+    // Configs from before versions existed hold the same fields, they only
+    // gain the version
+    |_| {},
+];
+
+/// Config file formats, detected from the file extension
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq)]
+pub enum Format {
+    Yaml,
+    Toml,
+}
+
+impl Format {
+    /// Picks the format from a path's extension, defaulting to YAML
+    pub fn from_path(path: &Path) -> Format {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some(ext) if ext.eq_ignore_ascii_case("toml") => Format::Toml,
+            _ => Format::Yaml,
+        }
+    }
+
+    fn extension(self) -> &'static str {
+        match self {
+            Format::Yaml => "yaml",
+            Format::Toml => "toml",
+        }
+    }
+}
+
+/// A place saved under a short name with `locations add`, used with --location
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SavedLocation {
+    pub latitude: f64,
+    pub longitude: f64,
+    /// Place name from geocoding, for display
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Config {
+    /// Format version the file was written in, see `CONFIG_VERSION`
+    #[serde(default)]
+    pub version: u32,
+    /// May be empty when the key is in the keyring
+    #[serde(default)]
+    pub api_key: String,
+    pub latitude: f64,
+    pub longitude: f64,
+    pub units: String,
+    /// ISO 3166 country code used for postal codes given without one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub country: Option<String>,
+    /// Language for condition descriptions and labels, e.g. "de" or "pt_br"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lang: Option<String>,
+    /// Clock style for sunrise, sunset and update times, "12h" or "24h"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub time_format: Option<TimeFormat>,
+    /// Seconds to wait for a connection and then for a response
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub connect_timeout: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub read_timeout: Option<f64>,
+    /// Number of retries after a timeout, 429 or server error
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retries: Option<u32>,
+    /// Proxy for all requests, e.g. "http://proxy:3128" or "socks5://localhost:1080"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub proxy: Option<String>,
+    /// OpenWeatherMap API base URL, for proxies, caching gateways or test servers
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub api_base: Option<String>,
+    /// API call limits of the key's plan, used to throttle and warn
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub calls_per_minute: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub calls_per_month: Option<u64>,
+    /// Per-measurement units, overriding the `units` preset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub temperature_unit: Option<TemperatureUnit>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub wind_unit: Option<SpeedUnit>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pressure_unit: Option<PressureUnit>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub precipitation_unit: Option<PrecipitationUnit>,
+    /// Rows of the weather card, in order, e.g. [temp, humidity, wind]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fields: Option<Vec<Field>>,
+    /// Describes the wind strength on the Beaufort scale, e.g. "fresh breeze"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub beaufort: Option<bool>,
+    /// Plain "Label: value" output without art or colors, like --plain
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub plain: Option<bool>,
+    /// Rhai script that renders the current weather instead of the card,
+    /// relative to the config file's directory
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub render_script: Option<PathBuf>,
+    /// Where the current weather comes from: "openweathermap", the default,
+    /// "open-meteo" or the name of one of the `providers`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub provider: Option<String>,
+    /// Personal weather station shown with the provider's weather
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub station: Option<Station>,
+    /// InfluxDB server for --export influx
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub influx: Option<Influx>,
+    /// Saved places by name, e.g. "home" or "cabin"
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub locations: BTreeMap<String, SavedLocation>,
+    /// Plugin programs by name, selected with `provider`
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub providers: BTreeMap<String, Plugin>,
+    /// Named sets of settings that override the ones above, selected with --profile.
+    /// Kept last since TOML tables have to follow plain values.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub profiles: BTreeMap<String, Map<String, Value>>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            version: CONFIG_VERSION,
+            api_key: "".to_string(),
+            latitude: 0.0,
+            longitude: 0.0,
+            units: "imperial".to_string(),
+            country: None,
+            lang: None,
+            time_format: None,
+            connect_timeout: None,
+            read_timeout: None,
+            retries: None,
+            proxy: None,
+            api_base: None,
+            calls_per_minute: None,
+            calls_per_month: None,
+            temperature_unit: None,
+            wind_unit: None,
+            pressure_unit: None,
+            precipitation_unit: None,
+            fields: None,
+            beaufort: None,
+            plain: None,
+            render_script: None,
+            provider: None,
+            station: None,
+            influx: None,
+            locations: BTreeMap::new(),
+            providers: BTreeMap::new(),
+            profiles: BTreeMap::new(),
+        }
+    }
+}
+
+impl Config {
+    /// Language to display in, from the config or else the system locale
+    pub fn lang(&self) -> String {
+        self.lang
+            .clone()
+            .or_else(i18n::locale_language)
+            .unwrap_or_else(|| "en".to_string())
+    }
+
+    /// Clock style from the config, defaulting to 12-hour in the US and
+    /// 24-hour elsewhere
+    pub fn time_format(&self) -> TimeFormat {
+        self.time_format.unwrap_or_else(|| match self.country.clone().or_else(location::locale_country).as_deref() {
+            Some("US") => TimeFormat::TwelveHour,
+            _ => TimeFormat::TwentyFourHour,
+        })
+    }
+
+    /// Layout of the weather card, with the default rows unless the config
+    /// lists some
+    pub fn card_options(&self) -> CardOptions {
+        CardOptions {
+            fields: self.fields.clone().unwrap_or_else(|| DEFAULT_FIELDS.to_vec()),
+            lang: self.lang(),
+            time_format: self.time_format(),
+            beaufort: self.beaufort.unwrap_or(false),
+            width: None,
+            plain: self.plain(),
+        }
+    }
+
+    /// Whether output should be plain lines, for screen readers and logs
+    pub fn plain(&self) -> bool {
+        self.plain.unwrap_or(false)
+    }
+
+    /// Where the current weather comes from, OpenWeatherMap unless
+    /// `provider` names another
+    pub fn provider(&self) -> Result<Provider<'_>, WeatherError> {
+        match self.provider.as_deref() {
+            None | Some(provider::OPENWEATHERMAP) => Ok(Provider::OpenWeatherMap),
+            Some(provider::OPEN_METEO) => Ok(Provider::OpenMeteo),
+            Some(name) => match self.providers.get_key_value(name) {
+                Some((name, plugin)) => Ok(Provider::Plugin(name, plugin)),
+                None => Err(WeatherError::Config(format!(
+                    "provider \"{}\" isn't {}, {} or one of the configured providers",
+                    name,
+                    provider::OPENWEATHERMAP,
+                    provider::OPEN_METEO
+                ))),
+            },
+        }
+    }
+
+    /// HTTP timeouts and retries, with defaults for anything not configured
+    pub fn http_settings(&self) -> HttpSettings {
+        let defaults = HttpSettings::default();
+        HttpSettings {
+            connect_timeout: self.connect_timeout.map_or(defaults.connect_timeout, Duration::from_secs_f64),
+            read_timeout: self.read_timeout.map_or(defaults.read_timeout, Duration::from_secs_f64),
+            retries: self.retries.unwrap_or(defaults.retries),
+            proxy: self.proxy.clone(),
+        }
+    }
+
+    /// API call limits, with the free plan's for anything not configured
+    pub fn quota_limits(&self) -> Limits {
+        let defaults = Limits::default();
+        Limits {
+            per_minute: self.calls_per_minute.unwrap_or(defaults.per_minute),
+            per_month: self.calls_per_month.unwrap_or(defaults.per_month),
+        }
+    }
+
+    /// Resolves display units from the `units` preset and per-measurement
+    /// overrides. A preset given on the command line replaces both.
+    pub fn units(&self, preset: Option<&str>) -> Units {
+        if let Some(preset) = preset {
+            return Units::preset(preset);
+        }
+
+        let mut units = Units::preset(&self.units);
+        if let Some(unit) = self.temperature_unit {
+            units.temperature = unit;
+        }
+        if let Some(unit) = self.wind_unit {
+            units.wind = unit;
+        }
+        if let Some(unit) = self.pressure_unit {
+            units.pressure = unit;
+        }
+        if let Some(unit) = self.precipitation_unit {
+            units.precipitation = unit;
+        }
+        units
+    }
+
+    /// Returns this config with the named profile's settings layered on top
+    pub fn with_profile(&self, name: &str) -> Result<Config, Box<dyn Error>> {
+        let overrides = self.profiles.get(name).ok_or_else(|| {
+            let names: Vec<&str> = self.profiles.keys().map(String::as_str).collect();
+            format!("Profile \"{}\" not found (available: {})", name, names.join(", "))
+        })?;
+
+        let mut merged = serde_json::to_value(self)?;
+        if let Value::Object(fields) = &mut merged {
+            fields.extend(overrides.clone());
+        }
+        Ok(serde_json::from_value(merged)?)
+    }
+
+    /// Returns this config with `RUSTY_WEATHER_<FIELD>` environment variables
+    /// layered on top, e.g. `RUSTY_WEATHER_API_KEY` or `RUSTY_WEATHER_LAT`.
+    pub fn with_env_overrides(&self) -> Result<Config, Box<dyn Error>> {
+        let mut merged = serde_json::to_value(self)?;
+        let Value::Object(fields) = &mut merged else {
+            return Err("Config is not a mapping".into());
+        };
+
+        for (var, value) in std::env::vars() {
+            let Some(field) = var.strip_prefix(ENV_PREFIX) else {
+                continue;
+            };
+            let key = match field.to_lowercase().as_str() {
+                "lat" => "latitude".to_string(),
+                "lon" => "longitude".to_string(),
+                other => other.to_string(),
+            };
+
+            let parsed = typed_value(&key, fields.get(&key), &value).map_err(|e| format!("{} {}", var, e))?;
+            fields.insert(key, parsed);
+        }
+
+        Ok(serde_json::from_value(merged)?)
+    }
+
+    /// Sets a single field from its string form, in the named profile if given
+    pub fn set_value(&mut self, profile: Option<&str>, key: &str, value: &str) -> Result<(), Box<dyn Error>> {
+        if ["profiles", "locations", "providers", "station", "influx"].contains(&key) {
+            return Err(format!("{} can't be set as a single value", key).into());
+        }
+
+        let current = serde_json::to_value(self.with_optional_profile(profile)?)?;
+        let parsed = typed_value(key, current.get(key), value).map_err(|e| format!("{} {}", key, e))?;
+
+        let invalid = |e: Box<dyn Error>| format!("Invalid value \"{}\" for {}: {}", value, key, e);
+        let mut updated = self.clone();
+        match profile {
+            Some(name) => {
+                updated.profiles.entry(name.to_string()).or_default().insert(key.to_string(), parsed);
+            }
+            None => {
+                let mut fields = serde_json::to_value(&updated)?;
+                fields[key] = parsed;
+                updated = serde_json::from_value(fields).map_err(|e| invalid(e.into()))?;
+            }
+        }
+
+        // Unknown fields are dropped on deserialization, so check the key survived
+        let applied = updated.with_optional_profile(profile).map_err(invalid)?;
+        let applied = serde_json::to_value(applied)?;
+        if applied.get(key).is_none() {
+            return Err(format!("Unknown config field \"{}\"", key).into());
+        }
+
+        *self = updated;
+        Ok(())
+    }
+
+    /// Returns this config with the named profile applied, if any
+    pub fn with_optional_profile(&self, profile: Option<&str>) -> Result<Config, Box<dyn Error>> {
+        match profile {
+            Some(name) => self.with_profile(name),
+            None => Ok(self.clone()),
+        }
+    }
+
+    /// Returns this config as a generic value with API keys hidden, for display
+    pub fn redacted(&self) -> Result<Value, Box<dyn Error>> {
+        let redact = |fields: &mut Map<String, Value>| {
+            if let Some(Value::String(key)) = fields.get_mut("api_key") {
+                if !key.is_empty() {
+                    *key = "<redacted>".to_string();
+                }
+            }
+        };
+
+        let mut value = serde_json::to_value(self)?;
+        if let Value::Object(fields) = &mut value {
+            redact(fields);
+            if let Some(Value::Object(station)) = fields.get_mut("station") {
+                redact(station);
+            }
+            if let Some(Value::String(token)) = fields.get_mut("influx").and_then(|influx| influx.get_mut("token")) {
+                *token = "<redacted>".to_string();
+            }
+            if let Some(Value::Object(profiles)) = fields.get_mut("profiles") {
+                profiles.values_mut().filter_map(Value::as_object_mut).for_each(redact);
+            }
+        }
+        Ok(value)
+    }
+
+    /// Returns this config without its API keys and tokens, for sharing
+    pub fn without_credentials(mut self) -> Config {
+        self.api_key.clear();
+        if let Some(Station { source: StationSource::Wunderground { api_key, .. }, .. }) = &mut self.station {
+            api_key.clear();
+        }
+        if let Some(influx) = &mut self.influx {
+            influx.token = None;
+        }
+        for profile in self.profiles.values_mut() {
+            profile.remove("api_key");
+        }
+        self
+    }
+
+    /// Checks field types and ranges, returning a description of each problem
+    pub fn validate(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+
+        if self.api_key.is_empty() {
+            problems.push("api_key is not set (and not in the keyring)".to_string());
+        } else if self.api_key.len() != 32 || !self.api_key.chars().all(|c| c.is_ascii_hexdigit()) {
+            problems.push("api_key doesn't look like an OpenWeatherMap key (32 hex characters)".to_string());
+        }
+        if !(-90.0..=90.0).contains(&self.latitude) {
+            problems.push(format!("latitude must be between -90 and 90, got {}", self.latitude));
+        }
+        if !(-180.0..=180.0).contains(&self.longitude) {
+            problems.push(format!("longitude must be between -180 and 180, got {}", self.longitude));
+        }
+        if !PRESETS.contains(&self.units.as_str()) {
+            problems.push(format!("units must be imperial, metric or standard, got \"{}\"", self.units));
+        }
+        for (name, timeout) in [("connect_timeout", self.connect_timeout), ("read_timeout", self.read_timeout)] {
+            if timeout.is_some_and(|t| !(t > 0.0 && t.is_finite())) {
+                problems.push(format!("{} must be a positive number of seconds", name));
+            }
+        }
+        if let Some(base) = &self.api_base {
+            if !base.starts_with("http://") && !base.starts_with("https://") {
+                problems.push(format!("api_base must be an http:// or https:// URL, got \"{}\"", base));
+            }
+        }
+        if let Err(e) = self.provider() {
+            problems.push(e.to_string());
+        }
+        if let Some(Station { source: StationSource::Ecowitt { url }, .. }) = &self.station {
+            if !url.starts_with("http://") && !url.starts_with("https://") {
+                problems.push(format!("station url must be an http:// or https:// URL, got \"{}\"", url));
+            }
+        }
+        if let Some(influx) = &self.influx {
+            if !influx.url.starts_with("http://") && !influx.url.starts_with("https://") {
+                problems.push(format!("influx url must be an http:// or https:// URL, got \"{}\"", influx.url));
+            }
+            if influx.bucket.is_none() && influx.database.is_none() {
+                problems.push("influx needs a bucket (InfluxDB 2) or a database (InfluxDB 1)".to_string());
+            }
+        }
+        for (name, plugin) in &self.providers {
+            if plugin.command.trim().is_empty() {
+                problems.push(format!("provider \"{}\" has no command", name));
+            }
+        }
+        for (name, place) in &self.locations {
+            if !(-90.0..=90.0).contains(&place.latitude) || !(-180.0..=180.0).contains(&place.longitude) {
+                problems.push(format!("location \"{}\" has out of range coordinates", name));
+            }
+        }
+        if let Some(country) = &self.country {
+            if country.len() != 2 || !country.chars().all(|c| c.is_ascii_alphabetic()) {
+                problems.push(format!("country must be a two-letter code like US or GB, got \"{}\"", country));
+            }
+        }
+
+        problems
+    }
+
+    /// Returns this config with an empty API key filled in from the system
+    /// keyring, preferring the profile's own entry over the default one.
+    pub fn with_keyring_fallback(mut self, profile: Option<&str>) -> Config {
+        if self.api_key.is_empty() {
+            let stored = secret::get_api_key(profile).or_else(|| profile.and_then(|_| secret::get_api_key(None)));
+            if let Some(api_key) = stored {
+                self.api_key = api_key;
+            }
+        }
+        self
+    }
+
+    /// Saves the settings where `updated` differs from this config as the named profile
+    pub fn set_profile(&mut self, name: &str, updated: &Config) -> Result<(), Box<dyn Error>> {
+        let (Value::Object(base), Value::Object(updated)) = (serde_json::to_value(&*self)?, serde_json::to_value(updated)?) else {
+            return Err("Config is not a mapping".into());
+        };
+
+        let overrides = updated
+            .into_iter()
+            .filter(|(key, value)| key != "profiles" && key != "locations" && key != "providers" && base.get(key) != Some(value))
+            .collect();
+        self.profiles.insert(name.to_string(), overrides);
+        Ok(())
+    }
+}
+
+/// Parses a string into the same JSON type as the field it replaces, so
+/// numbers stay numbers. List fields take comma-separated items, and fields
+/// that aren't set yet are read as a bool or number when they look like one.
+fn typed_value(key: &str, current: Option<&Value>, value: &str) -> Result<Value, String> {
+    if LIST_FIELDS.contains(&key) {
+        return Ok(value.split(',').map(str::trim).filter(|item| !item.is_empty()).collect());
+    }
+    match current {
+        Some(Value::Number(_)) => number(value).ok_or_else(|| "must be a number".to_string()),
+        Some(Value::Bool(_)) => value.parse::<bool>().map(Value::from).map_err(|_| "must be true or false".to_string()),
+        Some(_) => Ok(Value::from(value)),
+        None => Ok(match (value.parse::<bool>(), number(value)) {
+            (Ok(flag), _) => Value::from(flag),
+            (_, Some(number)) => number,
+            _ => Value::from(value),
+        }),
+    }
+}
+
+/// Reads a number, as an integer where it is one, since integer fields like
+/// `retries` don't accept `2.0`
+fn number(value: &str) -> Option<Value> {
+    value
+        .parse::<i64>()
+        .map(Value::from)
+        .or_else(|_| value.parse::<u64>().map(Value::from))
+        .or_else(|_| value.parse::<f64>().map(Value::from))
+        .ok()
+}
+
+/// Formats a generic value in the given config format
+pub fn to_string(value: &Value, format: Format) -> Result<String, Box<dyn Error>> {
+    match format {
+        Format::Yaml => Ok(serde_yaml::to_string(value)?),
+        Format::Toml => Ok(toml::to_string(value)?),
+    }
+}
+
+/// Returns the config path in the platform config directory
+/// (`$XDG_CONFIG_HOME/rusty_weather/config.yaml` on Linux, or `config.toml`
+/// if that exists), moving over a config file left in the working directory
+/// by older versions.
+pub fn default_config_path() -> Result<PathBuf, Box<dyn Error>> {
+    let dirs = ProjectDirs::from("", "", "rusty_weather").ok_or("Could not determine the config directory")?;
+    let toml_path = dirs.config_dir().join(TOML_CONFIG_FILE);
+    if toml_path.exists() {
+        return Ok(toml_path);
+    }
+    let path = dirs.config_dir().join(CONFIG_FILE);
+
+    // Any project can have a config.yaml, so only move one that reads as ours
+    let legacy = Path::new(CONFIG_FILE);
+    if !path.exists() && is_config(legacy) {
+        fs::create_dir_all(dirs.config_dir())?;
+        fs::copy(legacy, &path)?;
+        // The original goes only once the copy is known to be intact
+        if fs::read(&path)? != fs::read(legacy)? || !is_config(&path) {
+            fs::remove_file(&path)?;
+            return Err(format!("Couldn't copy {} to {}", legacy.display(), path.display()).into());
+        }
+        fs::remove_file(legacy)?;
+        eprintln!("Moved {} to {}", legacy.display(), path.display());
+    }
+
+    Ok(path)
+}
+
+/// Whether a file exists and reads as a YAML config
+fn is_config(path: &Path) -> bool {
+    fs::read_to_string(path).is_ok_and(|contents| parse_config(&contents, Format::Yaml).is_ok())
+}
+
+/// Loads the configuration, falling back to defaults if the file doesn't exist
+pub fn load_config(path: &Path) -> Result<Config, Box<dyn Error>> {
+    if !path.exists() {
+        eprintln!("No config file at {}, using the defaults", path.display());
+        return Ok(Config::default());
+    }
+
+    let mut file = File::open(path)?;
+    let mut contents = String::new();
+    file.read_to_string(&mut contents)?;
+    parse_config(&contents, Format::from_path(path))
+}
+
+/// Reads a config in either format, migrating it from older versions
+fn parse_config(contents: &str, format: Format) -> Result<Config, Box<dyn Error>> {
+    let value: Value = match format {
+        Format::Yaml => serde_yaml::from_str(contents)?,
+        Format::Toml => toml::from_str(contents)?,
+    };
+    let Value::Object(mut fields) = value else {
+        return Err("Config is not a mapping".into());
+    };
+
+    let version = fields.get("version").and_then(Value::as_u64).unwrap_or(0);
+    match usize::try_from(version).ok().and_then(|version| MIGRATIONS.get(version..)) {
+        Some(migrations) => {
+            migrations.iter().for_each(|migrate| migrate(&mut fields));
+            if !migrations.is_empty() {
+                tracing::info!(from = version, to = CONFIG_VERSION, "migrated config");
+            }
+            fields.insert("version".to_string(), Value::from(CONFIG_VERSION));
+        }
+        None => tracing::warn!(
+            version,
+            supported = CONFIG_VERSION,
+            "config is from a newer rusty_weather, settings this version doesn't know are ignored"
+        ),
+    }
+
+    let config: Config = serde_json::from_value(Value::Object(fields.clone()))?;
+    // Fields that didn't survive reading would be gone the next time the file is saved
+    let known = serde_json::to_value(&config)?;
+    for (field, setting) in &fields {
+        let empty = setting.is_null() || setting.as_object().is_some_and(Map::is_empty);
+        if !empty && known.get(field).is_none() {
+            tracing::warn!(field, "unknown config field, ignored");
+        }
+    }
+    Ok(config)
+}
+
+/// Reads a config written by `config export`, or any config file, with "-"
+/// for YAML on stdin. An import without an API key keeps `current`'s.
+pub fn import_config(path: &Path, current: &Config) -> Result<Config, Box<dyn Error>> {
+    let (contents, format) = match path.to_str() {
+        Some("-") => {
+            let mut contents = String::new();
+            std::io::stdin().read_to_string(&mut contents)?;
+            (contents, Format::Yaml)
+        }
+        _ => (fs::read_to_string(path)?, Format::from_path(path)),
+    };
+    let mut imported = parse_config(&contents, format)?;
+    if imported.version > CONFIG_VERSION {
+        return Err(format!(
+            "{} is config version {}, but this rusty_weather only knows up to {}; upgrade it first",
+            path.display(),
+            imported.version,
+            CONFIG_VERSION
+        )
+        .into());
+    }
+    if imported.api_key.is_empty() {
+        imported.api_key = current.api_key.clone();
+    }
+    Ok(imported)
+}
+
+/// Saves the configuration, as YAML or TOML depending on the file extension.
+/// Configs from a newer version aren't saved, since that would drop the
+/// settings this version doesn't know.
+pub fn save_config(config: &Config, path: &Path) -> Result<(), Box<dyn Error>> {
+    if config.version > CONFIG_VERSION {
+        return Err(format!(
+            "{} was written by a newer rusty_weather (config version {}, this one knows up to {}), so it's left unchanged",
+            path.display(),
+            config.version,
+            CONFIG_VERSION
+        )
+        .into());
+    }
+    let contents = match Format::from_path(path) {
+        Format::Yaml => serde_yaml::to_string(config)?,
+        Format::Toml => toml::to_string(config)?,
+    };
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let mut file = OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(path)?;
+    file.write_all(contents.as_bytes())?;
+    Ok(())
+}
+
+/// Rewrites the config file at `path` in another format, next to the
+/// original, and removes the original. Returns the new path.
+pub fn convert_config(path: &Path, to: Option<Format>) -> Result<PathBuf, Box<dyn Error>> {
+    let from = Format::from_path(path);
+    let to = to.unwrap_or(match from {
+        Format::Yaml => Format::Toml,
+        Format::Toml => Format::Yaml,
+    });
+    if from == to {
+        return Err(format!("{} is already in {} format", path.display(), to.extension()).into());
+    }
+    if !path.exists() {
+        return Err(format!("{} not found", path.display()).into());
+    }
+
+    let config = load_config(path)?;
+    let converted = path.with_extension(to.extension());
+    save_config(&config, &converted)?;
+    fs::remove_file(path)?;
+    Ok(converted)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const OLD_CONFIG: &str = "api_key: abc\nlatitude: 42.81\nlongitude: -73.94\nunits: metric\n";
+
+    /// A directory of its own for a test's files, emptied first
+    fn scratch(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("rusty_weather-config-{}-{}", std::process::id(), name));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn migrations_bring_every_version_up_to_date() {
+        for version in 0..CONFIG_VERSION {
+            let config = parse_config(&format!("version: {}\n{}", version, OLD_CONFIG), Format::Yaml).unwrap();
+            assert_eq!(config.version, CONFIG_VERSION, "from version {}", version);
+            assert_eq!(config.api_key, "abc");
+            assert_eq!((config.latitude, config.longitude), (42.81, -73.94));
+        }
+    }
+
+    #[test]
+    fn configs_without_a_version_are_version_zero() {
+        let config = parse_config(OLD_CONFIG, Format::Yaml).unwrap();
+        assert_eq!(config.version, CONFIG_VERSION);
+        let config = parse_config("api_key = \"abc\"\nlatitude = 1.0\nlongitude = 2.0\nunits = \"metric\"\n", Format::Toml).unwrap();
+        assert_eq!(config.version, CONFIG_VERSION);
+    }
+
+    #[test]
+    fn newer_configs_keep_their_version_and_are_not_saved() {
+        let newer = CONFIG_VERSION + 1;
+        let config = parse_config(&format!("version: {}\n{}", newer, OLD_CONFIG), Format::Yaml).unwrap();
+        assert_eq!(config.version, newer);
+
+        let path = scratch("newer").join("config.yaml");
+        fs::write(&path, "version: 2\n").unwrap();
+        let error = save_config(&config, &path).unwrap_err().to_string();
+        assert!(error.contains("written by a newer rusty_weather"), "{}", error);
+        assert_eq!(fs::read_to_string(&path).unwrap(), "version: 2\n");
+        let _ = fs::remove_dir_all(path.parent().unwrap());
+    }
+
+    #[test]
+    fn saved_configs_read_back_in_both_formats() {
+        let dir = scratch("round-trip");
+        let config = parse_config(OLD_CONFIG, Format::Yaml).unwrap();
+        for name in ["config.yaml", "config.toml"] {
+            save_config(&config, &dir.join(name)).unwrap();
+            let saved = load_config(&dir.join(name)).unwrap();
+            assert_eq!((saved.version, saved.api_key.as_str(), saved.latitude), (CONFIG_VERSION, "abc", 42.81), "{}", name);
+        }
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn redacted_hides_the_api_key() {
+        let config = parse_config(OLD_CONFIG, Format::Yaml).unwrap();
+        assert_eq!(config.redacted().unwrap()["api_key"], "<redacted>");
+        assert!(config.without_credentials().api_key.is_empty());
+    }
+}
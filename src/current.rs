@@ -0,0 +1,261 @@
+use crate::comfort::FeelsLikeSource;
+use crate::error::WeatherError;
+use crate::http;
+use crate::i18n::{self, Label};
+use crate::layout::{self, Layout};
+use crate::model::{CurrentWeather, DailyForecast, Forecast, Wind};
+use crate::pollen;
+use crate::owm;
+use crate::time::{self, TimeFormat};
+use crate::units::Units;
+use crate::wind;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::cmp;
+use std::io::{self, IsTerminal};
+
+/// Rows the weather card can show, set with `fields` in the config
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Field {
+    Temp,
+    FeelsLike,
+    Min,
+    Max,
+    Wind,
+    Humidity,
+    DewPoint,
+    Pressure,
+    Visibility,
+    Clouds,
+    Conditions,
+    /// Sunrise and sunset on one row
+    Sun,
+    Updated,
+    /// Tree, grass and weed pollen levels, where there's pollen data
+    Pollen,
+}
+
+/// Longest city name shown on one line under the art, longer ones wrap
+const CITY_WIDTH: usize = 16;
+/// Width of each day's column in the wide layout
+const FORECAST_COLUMN: usize = 14;
+/// Space between the card and the forecast columns
+const FORECAST_GAP: usize = 4;
+
+/// How the weather card is laid out, from the config
+pub struct CardOptions {
+    pub fields: Vec<Field>,
+    pub lang: String,
+    pub time_format: TimeFormat,
+    /// Whether to describe the wind on the Beaufort scale
+    pub beaufort: bool,
+    /// Terminal width to fit the card to, when known
+    pub width: Option<usize>,
+    /// Plain "Label: value" lines, without art, alignment or symbols
+    pub plain: bool,
+}
+
+/// Rows shown when the config doesn't list any, in this order
+pub const DEFAULT_FIELDS: [Field; 13] = [
+    Field::Temp,
+    Field::FeelsLike,
+    Field::Min,
+    Field::Max,
+    Field::Wind,
+    Field::Humidity,
+    Field::DewPoint,
+    Field::Pressure,
+    Field::Visibility,
+    Field::Clouds,
+    Field::Conditions,
+    Field::Sun,
+    Field::Updated,
+];
+
+/// Fetches weather data from OpenWeatherMap API and returns JSON. The
+/// location is given as query parameters, `lat` and `lon` or `zip`.
+pub fn get_weather(api_key: &str, location: &[(&str, &str)], lang: &str) -> Result<Value, WeatherError> {
+    // Always fetch metric, display units are converted locally
+    let mut params = location.to_vec();
+    params.extend([("appid", api_key), ("units", "metric"), ("lang", lang)]);
+    let url = http::with_query(&owm::url("/data/2.5/weather"), &params);
+
+    let response = http::get(&url)?.into_string()?;
+    let json: Value = serde_json::from_str(&response)?;
+
+    Ok(json)
+}
+
+/// ASCII art for a condition group, four rows of equal width, blank for
+/// conditions without art
+pub fn condition_art(condition: &str) -> [&'static str; 4] {
+    match condition {
+        "Clear" => [" \\ | / ", "- ( ) -", " / | \\ ", "       "],
+        "Clouds" => ["    .-.   ", " .-(   ). ", "(________)", "          "],
+        "Rain" => ["' '' '", " ' '' ", "''  ' ", "      "],
+        "Snow" => ["*  * *", " *  * ", "* *  *", "      "],
+        _ => ["   ", "   ", "   ", "   "],
+    }
+}
+
+/// Prints a line in reverse video above the card, e.g. to flag old data
+pub fn print_banner(text: &str, plain: bool) {
+    match io::stdout().is_terminal() && !plain {
+        true => println!("\x1b[7m {} \x1b[0m", text),
+        false if plain => println!("{}", text),
+        false => println!("*** {} ***", text),
+    }
+}
+
+/// Print formatted response, arranged for the terminal width. The wide
+/// layout shows `forecast` beside the card when there is one, and the
+/// pollen row needs `pollen`.
+pub fn print_weather_info(
+    weather: &CurrentWeather,
+    forecast: Option<&Forecast>,
+    pollen: Option<&pollen::Counts>,
+    units: &Units,
+    options: &CardOptions,
+) {
+    let rows = card_rows(weather, pollen, units, options);
+    let layout = layout::for_width(options.width);
+    let width = options.width.unwrap_or(usize::MAX);
+
+    if options.plain {
+        println!("{}", weather.city);
+        rows.iter().for_each(|row| println!("{}", row));
+        return;
+    }
+
+    let lines: Vec<String> = match layout {
+        Layout::Compact => std::iter::once(&weather.city)
+            .chain(&rows)
+            .map(|line| layout::truncate(line, width))
+            .collect(),
+        Layout::Normal | Layout::Wide => art_card(weather, rows),
+    };
+
+    let card_width = lines.iter().map(|line| layout::width(line)).max().unwrap_or(0);
+    let columns = match (layout, forecast) {
+        (Layout::Wide, Some(forecast)) => {
+            let days = (width.saturating_sub(card_width + FORECAST_GAP) / (FORECAST_COLUMN + 2)).min(forecast.daily.len());
+            forecast_columns(forecast, days, units)
+        }
+        _ => Vec::new(),
+    };
+
+    for (i, line) in lines.iter().enumerate() {
+        let line = match columns.get(i) {
+            Some(column) => format!("{}{:pad$}{}", line, "", column, pad = card_width - layout::width(line) + FORECAST_GAP),
+            None => line.clone(),
+        };
+        println!("{}", line.trim_end());
+    }
+}
+
+/// "Label: value" rows for the configured fields
+fn card_rows(weather: &CurrentWeather, pollen: Option<&pollen::Counts>, units: &Units, options: &CardOptions) -> Vec<String> {
+    let label = |l| i18n::label(&options.lang, l);
+    let color = io::stdout().is_terminal() && !options.plain;
+    let local_time = |timestamp| time::format_time(timestamp, weather.utc_offset, options.time_format);
+    options
+        .fields
+        .iter()
+        .filter_map(|field| {
+            let (name, value) = match field {
+                Field::Temp => (Label::Temperature, units.format_temperature(weather.temp)),
+                Field::FeelsLike => (Label::FeelsLike, format_feels_like(weather, units)),
+                Field::Min => (Label::Min, units.format_temperature(weather.temp_min)),
+                Field::Max => (Label::Max, units.format_temperature(weather.temp_max)),
+                Field::Wind => (Label::WindSpeed, format_wind(&weather.wind, units, options)),
+                Field::Humidity => (Label::Humidity, format!("{:.0}%", weather.humidity)),
+                Field::DewPoint => (Label::DewPoint, units.format_temperature(weather.dew_point)),
+                Field::Pressure => (Label::Pressure, units.format_pressure(weather.pressure)),
+                Field::Visibility => (Label::Visibility, units.format_distance(weather.visibility?)),
+                Field::Clouds => (Label::CloudCover, format!("{:.0}%", weather.clouds)),
+                Field::Conditions => (Label::Conditions, weather.description.clone()),
+                // Providers that don't report the sun leave both at zero
+                Field::Sun if weather.sunrise == 0 && weather.sunset == 0 => return None,
+                Field::Sun => {
+                    let sunset = format!("{}: {}", label(Label::Sunset), local_time(weather.sunset));
+                    let separator = if options.plain { "\n" } else { "  " };
+                    (Label::Sunrise, format!("{}{}{}", local_time(weather.sunrise), separator, sunset))
+                }
+                Field::Updated => (Label::Updated, local_time(weather.updated)),
+                Field::Pollen => (Label::Pollen, pollen?.summary(color)),
+            };
+            Some(format!("{}: {}", label(name), value))
+        })
+        .collect()
+}
+
+/// The art with the city under it, beside the rows. Long city names wrap
+/// within the art column so the rows stay aligned.
+fn art_card(weather: &CurrentWeather, mut rows: Vec<String>) -> Vec<String> {
+    let art = condition_art(&weather.condition);
+    let width = cmp::max(art[3].chars().count(), cmp::min(weather.city.chars().count(), CITY_WIDTH));
+    let left: Vec<String> = art[..3]
+        .iter()
+        .map(|line| line.to_string())
+        .chain(layout::wrap(&weather.city, width))
+        .collect();
+    while rows.len() < left.len() {
+        rows.push(String::new());
+    }
+
+    rows.iter()
+        .enumerate()
+        .map(|(i, row)| {
+            let left = left.get(i).map_or("", String::as_str);
+            format!("{:^width$} | {}", left, row, width = width).trim_end().to_string()
+        })
+        .collect()
+}
+
+/// Four lines per day, each `FORECAST_COLUMN` wide: the day, low and high,
+/// conditions and precipitation
+fn forecast_columns(forecast: &Forecast, days: usize, units: &Units) -> Vec<String> {
+    let days = &forecast.daily[..days];
+    let cell = |text: String| format!("{:<width$}", layout::truncate(&text, FORECAST_COLUMN), width = FORECAST_COLUMN);
+    let line = |text: &dyn Fn(&DailyForecast) -> String| days.iter().map(|day| cell(text(day))).collect::<Vec<_>>().join("  ");
+    vec![
+        line(&|day| format!("{} {}", day.weekday, &day.date[5..])),
+        line(&|day| {
+            let (min, max) = (units.temperature(day.temp.min), units.temperature(day.temp.max));
+            format!("{:.0} / {:.0}{}", min, max, units.temperature_symbol())
+        }),
+        line(&|day| day.description.clone()),
+        line(&|day| format!("{:.0}% {}", day.pop * 100.0, units.format_precipitation(day.precipitation))),
+    ]
+}
+
+/// Wind speed with the compass direction it comes from, plus gusts when
+/// they're well above the sustained speed, e.g. "5.2 m/s ↘ NW, gusts 11.0 m/s"
+fn format_wind(wind: &Wind, units: &Units, options: &CardOptions) -> String {
+    let mut text = units.format_wind(wind.speed);
+    match wind::is_calm(wind.speed) {
+        true => {}
+        // Screen readers spell out the arrow, the compass point says enough
+        false if options.plain => text = format!("{} from {}", text, wind::compass(wind.deg)),
+        false => text = format!("{} {} {}", text, wind::arrow(wind.deg), wind::compass(wind.deg)),
+    }
+    if let Some(gust) = wind.gust.filter(|&gust| wind::is_gusty(wind.speed, gust)) {
+        text = format!("{}, gusts {}", text, units.format_wind(gust));
+    }
+    if options.beaufort {
+        let (force, description) = wind::beaufort(wind.speed);
+        text = format!("{} ({}, force {})", text, description, force);
+    }
+    text
+}
+
+/// Feels-like temperature, naming the formula when it was computed locally
+fn format_feels_like(weather: &CurrentWeather, units: &Units) -> String {
+    let value = units.format_temperature(weather.feels_like);
+    match weather.feels_like_source {
+        FeelsLikeSource::HeatIndex => format!("{} (heat index)", value),
+        FeelsLikeSource::WindChill => format!("{} (wind chill)", value),
+        FeelsLikeSource::Reported | FeelsLikeSource::Air => value,
+    }
+}
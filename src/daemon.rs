@@ -0,0 +1,119 @@
+use crate::error::WeatherError;
+use crate::schedule::{self, Conditions, Policy};
+use directories::ProjectDirs;
+use serde_json::{json, Map, Value};
+use std::error::Error;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tracing::{info, warn};
+
+/// A place the daemon keeps fresh, under its name in the status file
+pub struct Place {
+    pub name: String,
+    pub lat: f64,
+    pub lon: f64,
+    /// Follows the device (--here or --gpsd), so it's located again on every refresh
+    pub live: bool,
+}
+
+/// Parses an interval like "90s", "10m" or "1h"; a bare number is minutes
+pub fn parse_interval(value: &str) -> Result<Duration, String> {
+    let value = value.trim();
+    let split = value.find(|c: char| !c.is_ascii_digit()).unwrap_or(value.len());
+    let (number, unit) = value.split_at(split);
+    let number: u64 = number.parse().map_err(|_| format!("\"{}\" isn't an interval like 30s, 10m or 1h", value))?;
+    let seconds = match unit {
+        "s" => number,
+        "" | "m" => number * 60,
+        "h" => number * 3600,
+        _ => return Err(format!("Unknown interval unit \"{}\", use s, m or h", unit)),
+    };
+    if seconds < 10 {
+        return Err("The interval has to be at least 10s".to_string());
+    }
+    Ok(Duration::from_secs(seconds))
+}
+
+/// Default status file, in the runtime directory where there is one
+pub fn default_status_path() -> Option<PathBuf> {
+    let dirs = ProjectDirs::from("", "", "rusty_weather")?;
+    Some(dirs.runtime_dir().unwrap_or(dirs.data_dir()).join("status.json"))
+}
+
+/// One place's refreshed data: fields for its status file entry, and the
+/// conditions the schedule adapts to
+pub struct Refresh {
+    pub data: Map<String, Value>,
+    pub conditions: Conditions,
+}
+
+/// Refreshes every place and rewrites the status file with the results,
+/// then waits an interval `schedule::Policy` picks from the latest weather
+/// and power state, around `interval`. A place that fails to refresh keeps
+/// its last data, with the error next to it, so readers always have
+/// something to show.
+pub fn run(
+    places: &[Place],
+    interval: Duration,
+    path: &Path,
+    fetch: impl Fn(&Place) -> Result<Refresh, WeatherError>,
+) -> Result<(), WeatherError> {
+    let mut policy = Policy::new(interval);
+    let mut entries = Map::new();
+    loop {
+        let mut conditions = Vec::new();
+        for place in places {
+            let entry = entries
+                .entry(place.name.clone())
+                .or_insert_with(|| json!({ "latitude": place.lat, "longitude": place.lon }));
+            match fetch(place) {
+                Ok(refresh) => {
+                    if let Some(entry) = entry.as_object_mut() {
+                        entry.extend(refresh.data);
+                        entry.insert("fetched".to_string(), json!(now()));
+                        entry.remove("error");
+                    }
+                    conditions.push(Some(refresh.conditions));
+                }
+                Err(e) => {
+                    warn!(place = place.name, error = %e, "refresh failed");
+                    entry["error"] = json!(e.to_string());
+                    conditions.push(None);
+                }
+            }
+        }
+
+        let (wait, reason) = policy.next_interval(conditions, schedule::on_battery());
+        let status = json!({
+            "updated": now(),
+            "next_update": now() + wait.as_secs(),
+            "locations": entries,
+        });
+        write_atomically(path, &status)
+            .map_err(|e| WeatherError::Other(format!("Couldn't write {}: {}", path.display(), e)))?;
+        info!(path = %path.display(), places = places.len(), next_in_secs = wait.as_secs(), reason, "status written");
+        thread::sleep(wait);
+    }
+}
+
+/// Writes to a temporary file next to `path` and renames it over the old
+/// one, so a reader never sees a half-written file
+fn write_atomically(path: &Path, value: &Value) -> Result<(), Box<dyn Error>> {
+    let dir = path.parent().filter(|dir| !dir.as_os_str().is_empty()).unwrap_or(Path::new("."));
+    fs::create_dir_all(dir)?;
+    let name = path.file_name().ok_or("the status file needs a file name")?;
+    let temp = dir.join(format!(".{}.tmp", name.to_string_lossy()));
+
+    let mut file = fs::File::create(&temp)?;
+    file.write_all(serde_json::to_string_pretty(value)?.as_bytes())?;
+    file.sync_all()?;
+    fs::rename(&temp, path)?;
+    Ok(())
+}
+
+fn now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0)
+}
@@ -0,0 +1,115 @@
+use crate::http::HttpError;
+use std::error::Error;
+use std::fmt::Display;
+
+/// What went wrong in a run, grouped by what the user can do about it.
+/// Each kind has its own exit code so scripts can tell them apart.
+#[derive(Debug, thiserror::Error)]
+pub enum WeatherError {
+    /// The config file is missing, unreadable or incomplete
+    #[error("{0}")]
+    Config(String),
+    /// The request never got an answer
+    #[error("Network error: {0}. Check your connection and proxy settings.")]
+    Network(String),
+    /// The API rejected the key (HTTP 401)
+    #[error("The API key was rejected ({0}). Fix api_key in the config or run --setup.")]
+    Auth(String),
+    /// The location or resource doesn't exist (HTTP 404)
+    #[error("{0}")]
+    NotFound(String),
+    /// Too many calls (HTTP 429)
+    #[error("Rate limit reached ({0}). Try again in a minute; `rusty_weather quota` shows your usage.")]
+    RateLimit(String),
+    /// The API answered with something we couldn't read
+    #[error("Unexpected response from the API: {0}")]
+    Parse(String),
+    /// The service failed on its end (HTTP 5xx)
+    #[error("The service returned {0}. Try again later.")]
+    Server(String),
+    /// The API refused the request for some other reason, e.g. HTTP 400
+    #[error("The API refused the request ({0}).")]
+    Request(String),
+    /// Anything else, e.g. gpsd or terminal errors
+    #[error("{0}")]
+    Other(String),
+}
+
+impl WeatherError {
+    pub fn config(error: impl Display) -> WeatherError {
+        WeatherError::Config(error.to_string())
+    }
+
+    /// Process exit code, as listed in the README
+    pub fn exit_code(&self) -> u8 {
+        match self {
+            WeatherError::Other(_) => 1,
+            WeatherError::Config(_) => 3,
+            WeatherError::Network(_) => 4,
+            WeatherError::Auth(_) => 5,
+            WeatherError::NotFound(_) => 6,
+            WeatherError::RateLimit(_) => 7,
+            WeatherError::Parse(_) => 8,
+            WeatherError::Server(_) => 9,
+            WeatherError::Request(_) => 10,
+        }
+    }
+}
+
+impl From<HttpError> for WeatherError {
+    fn from(error: HttpError) -> WeatherError {
+        let message = error.to_string();
+        match error {
+            HttpError::Status { status: 401, .. } => WeatherError::Auth(message),
+            HttpError::Status { status: 404, .. } => WeatherError::NotFound(message),
+            HttpError::Status { status: 429, .. } => WeatherError::RateLimit(message),
+            HttpError::Status { status: 500.., .. } => WeatherError::Server(message),
+            // Without a readable message, part of the body is the best clue
+            HttpError::Status { status, body } => match message == format!("HTTP {}", status) && !body.trim().is_empty() {
+                true => WeatherError::Request(format!("{}: {}", message, snippet(&body))),
+                false => WeatherError::Request(message),
+            },
+            HttpError::Transport(_) => WeatherError::Network(message),
+        }
+    }
+}
+
+/// The start of a response body on one line
+fn snippet(body: &str) -> String {
+    const LENGTH: usize = 200;
+    let text = body.split_whitespace().collect::<Vec<_>>().join(" ");
+    match text.char_indices().nth(LENGTH) {
+        Some((end, _)) => format!("{}…", &text[..end]),
+        None => text,
+    }
+}
+
+impl From<serde_json::Error> for WeatherError {
+    fn from(error: serde_json::Error) -> WeatherError {
+        WeatherError::Parse(error.to_string())
+    }
+}
+
+impl From<std::string::FromUtf8Error> for WeatherError {
+    fn from(error: std::string::FromUtf8Error) -> WeatherError {
+        WeatherError::Parse(error.to_string())
+    }
+}
+
+/// Sorts errors from modules that return `Box<dyn Error>` by their source
+impl From<Box<dyn Error>> for WeatherError {
+    fn from(error: Box<dyn Error>) -> WeatherError {
+        let error = match error.downcast::<WeatherError>() {
+            Ok(error) => return *error,
+            Err(error) => error,
+        };
+        let error = match error.downcast::<HttpError>() {
+            Ok(error) => return (*error).into(),
+            Err(error) => error,
+        };
+        match error.downcast::<serde_json::Error>() {
+            Ok(error) => (*error).into(),
+            Err(error) => WeatherError::Other(error.to_string()),
+        }
+    }
+}
@@ -0,0 +1,102 @@
+use crate::error::WeatherError;
+use crate::http;
+use crate::model::CurrentWeather;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Measurement name used unless the config sets one
+const DEFAULT_MEASUREMENT: &str = "weather";
+
+/// Where --export sends observations
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq)]
+pub enum Sink {
+    /// InfluxDB line protocol over HTTP, set up under `influx` in the config
+    Influx,
+}
+
+/// An InfluxDB server to write observations to. InfluxDB 2 takes an
+/// organization, bucket and token, InfluxDB 1 a database name.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Influx {
+    /// Server URL, e.g. "http://localhost:8086"
+    pub url: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub org: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bucket: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub database: Option<String>,
+    /// Measurement the points go in, "weather" unless set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub measurement: Option<String>,
+    /// Tags added to every point, e.g. {host: pi, site: cabin}
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub tags: BTreeMap<String, String>,
+}
+
+impl Influx {
+    /// Formats an observation as a line of InfluxDB line protocol, in
+    /// metric units with the observation time in seconds. The location and
+    /// city become tags alongside the configured ones.
+    pub fn line(&self, location: &str, weather: &CurrentWeather) -> String {
+        let mut tags: BTreeMap<&str, &str> = self.tags.iter().map(|(key, value)| (key.as_str(), value.as_str())).collect();
+        tags.insert("location", location);
+        tags.insert("city", &weather.city);
+
+        let mut fields = vec![
+            ("temp", weather.temp),
+            ("feels_like", weather.feels_like),
+            ("humidity", weather.humidity),
+            ("dew_point", weather.dew_point),
+            ("pressure", weather.pressure),
+            ("wind_speed", weather.wind.speed),
+            ("wind_deg", weather.wind.deg),
+            ("clouds", weather.clouds),
+        ];
+        let optional = [("wind_gust", weather.wind.gust), ("visibility", weather.visibility), ("precipitation", weather.precipitation)];
+        fields.extend(optional.into_iter().filter_map(|(name, value)| Some((name, value?))));
+
+        let tags: String = tags
+            .iter()
+            .filter(|(_, value)| !value.is_empty())
+            .map(|(key, value)| format!(",{}={}", escape(key), escape(value)))
+            .collect();
+        let fields: Vec<String> = fields.iter().map(|(name, value)| format!("{}={}", name, value)).collect();
+        let measurement = self.measurement.as_deref().unwrap_or(DEFAULT_MEASUREMENT).replace(',', "\\,").replace(' ', "\\ ");
+        format!("{}{} {} {}", measurement, tags, fields.join(","), weather.updated)
+    }
+
+    /// Writes lines of line protocol in one request
+    pub fn write(&self, lines: &[String]) -> Result<(), WeatherError> {
+        let base = self.url.trim_end_matches('/');
+        let url = match (&self.bucket, &self.database) {
+            (Some(bucket), _) => {
+                let org = self.org.as_deref().unwrap_or("");
+                http::with_query(&format!("{}/api/v2/write", base), &[("org", org), ("bucket", bucket), ("precision", "s")])
+            }
+            (None, Some(database)) => http::with_query(&format!("{}/write", base), &[("db", database), ("precision", "s")]),
+            (None, None) => return Err(WeatherError::Config("influx needs a bucket (InfluxDB 2) or a database (InfluxDB 1)".to_string())),
+        };
+        // InfluxDB 1.8 takes the same header, with "user:password" as the token
+        let mut headers = vec![("Content-Type", "text/plain; charset=utf-8".to_string())];
+        headers.extend(self.token.as_ref().map(|token| ("Authorization", format!("Token {}", token))));
+        let headers: Vec<(&str, &str)> = headers.iter().map(|(name, value)| (*name, value.as_str())).collect();
+
+        let body = lines.join("\n");
+        http::post(&url, &headers, body.as_bytes()).map_err(|e| match e {
+            http::HttpError::Status { status, body } => {
+                WeatherError::Other(format!("InfluxDB rejected the write (HTTP {}): {}", status, body.trim()))
+            }
+            e => WeatherError::from(e),
+        })?;
+        Ok(())
+    }
+}
+
+/// Escapes a tag key or value, where commas, equals signs and spaces need
+/// a backslash
+fn escape(text: &str) -> String {
+    text.replace(',', "\\,").replace('=', "\\=").replace(' ', "\\ ")
+}
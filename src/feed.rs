@@ -0,0 +1,79 @@
+use crate::model::{Alert, Forecast};
+use crate::time;
+use crate::units::Units;
+
+/// Formats other than the report for publishing the forecast
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq)]
+pub enum Output {
+    /// An RSS 2.0 feed of the daily forecast and active alerts
+    Rss,
+}
+
+/// Builds an RSS feed with an item for each active alert followed by one
+/// for each day of the forecast. Items keep their guid as the forecast is
+/// updated, so readers show a day once rather than on every refresh.
+pub fn rss(forecast: &Forecast, alerts: &[Alert], lat: f64, lon: f64, units: &Units) -> String {
+    let now = time::rfc2822(time::now());
+    let link = format!("https://openweathermap.org/weathermap?zoom=10&amp;lat={}&amp;lon={}", lat, lon);
+    let place = format!("{:.3},{:.3}", lat, lon);
+
+    let mut items = Vec::new();
+    for alert in alerts {
+        let description = match alert.sender.is_empty() {
+            true => alert.description.clone(),
+            false => format!("{}\n\n{}", alert.description, alert.sender),
+        };
+        items.push(item(
+            &format!("⚠ {}", alert.event),
+            &description,
+            &format!("alert {} {} {}", place, alert.event, alert.start),
+            &time::rfc2822(alert.start),
+            &link,
+        ));
+    }
+    for day in &forecast.daily {
+        let title = format!(
+            "{} {}: {}, {} / {}",
+            day.weekday,
+            day.date,
+            day.description,
+            units.format_temperature(day.temp.max),
+            units.format_temperature(day.temp.min)
+        );
+        let mut description = format!(
+            "High {}, low {}, {:.0}% chance of precipitation",
+            units.format_temperature(day.temp.max),
+            units.format_temperature(day.temp.min),
+            day.pop * 100.0
+        );
+        if day.precipitation > 0.0 {
+            description.push_str(&format!(", {} expected", units.format_precipitation(day.precipitation)));
+        }
+        items.push(item(&title, &description, &format!("forecast {} {}", place, day.date), &now, &link));
+    }
+
+    format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<rss version=\"2.0\">\n<channel>\n<title>{}</title>\n<link>{}</link>\n<description>{}</description>\n<lastBuildDate>{}</lastBuildDate>\n<ttl>60</ttl>\n{}</channel>\n</rss>\n",
+        escape(&format!("Weather for {}", forecast.city)),
+        link,
+        escape(&format!("Daily forecast and weather alerts for {}", forecast.city)),
+        now,
+        items.concat()
+    )
+}
+
+fn item(title: &str, description: &str, guid: &str, date: &str, link: &str) -> String {
+    format!(
+        "<item>\n<title>{}</title>\n<description>{}</description>\n<link>{}</link>\n<guid isPermaLink=\"false\">{}</guid>\n<pubDate>{}</pubDate>\n</item>\n",
+        escape(title),
+        escape(description),
+        link,
+        escape(guid),
+        date
+    )
+}
+
+/// Escapes text for XML element content
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
@@ -0,0 +1,368 @@
+use crate::error::WeatherError;
+use crate::lightning::{self, Lightning};
+use crate::units::Units;
+use crate::{http, time, wind};
+use serde_json::Value;
+
+/// Humidity, in percent, at or below which dry fuels catch and spread fire
+/// easily. With wind above `CRITICAL_WIND` or gusts above `CRITICAL_GUST`,
+/// and no real rain lately, that's critical fire weather, after common
+/// National Weather Service red flag criteria.
+pub const CRITICAL_HUMIDITY: f64 = 25.0;
+/// Sustained wind in m/s, 15 mph
+pub const CRITICAL_WIND: f64 = 6.7;
+/// Gusts in m/s, 25 mph
+pub const CRITICAL_GUST: f64 = 11.2;
+/// Rain in mm over the last three days that's enough to dampen fuels
+const WETTING_RAIN: f64 = 2.5;
+/// Forecast convective energy in J/kg from which thunderstorms, and so
+/// lightning, become likely. It's a forecast, not observed strikes.
+const LIKELY_STORM_CAPE: f64 = 1000.0;
+/// Cloud-to-ground strikes nearby from which, with dry fuels, new fires
+/// are worth watching for
+const STRIKE_WARNING: usize = 1;
+
+/// The weather of an hour, in metric units
+pub struct Hour {
+    /// Local time, "YYYY-MM-DDTHH:MM"
+    pub time: String,
+    pub humidity: f64,
+    pub wind_speed: f64,
+    pub wind_gust: f64,
+    /// Convective available potential energy, J/kg
+    pub cape: f64,
+}
+
+impl Hour {
+    fn is_critical(&self) -> bool {
+        self.humidity <= CRITICAL_HUMIDITY && (self.wind_speed >= CRITICAL_WIND || self.wind_gust >= CRITICAL_GUST)
+    }
+}
+
+/// Fire weather at a location: the conditions now, how dry it's been and
+/// the next 24 hours
+pub struct FireWeather {
+    pub temp: f64,
+    pub humidity: f64,
+    pub wind_speed: f64,
+    pub wind_deg: f64,
+    pub wind_gust: Option<f64>,
+    /// Vapour pressure deficit in kPa, how strongly the air dries fuels
+    pub vapour_pressure_deficit: Option<f64>,
+    /// Rain over the last 72 hours, in mm
+    pub recent_rain: f64,
+    /// Days in a row before today with under 1 mm of rain, up to a week
+    pub dry_days: usize,
+    pub next_hours: Vec<Hour>,
+    /// Strikes nearby, filled in separately from `lightning::get_lightning`
+    pub lightning: Lightning,
+}
+
+/// Fetches current conditions, the past week's rain and the next day's
+/// humidity, wind and instability from Open-Meteo, which needs no API key
+pub fn get_fire_weather(lat: &f64, lon: &f64) -> Result<Value, WeatherError> {
+    let url = format!(
+        "https://api.open-meteo.com/v1/forecast?latitude={}&longitude={}&current=temperature_2m,relative_humidity_2m,wind_speed_10m,wind_direction_10m,wind_gusts_10m,vapour_pressure_deficit&hourly=relative_humidity_2m,wind_speed_10m,wind_gusts_10m,precipitation,cape&daily=precipitation_sum&past_days=7&forecast_days=2&wind_speed_unit=ms&timezone=auto",
+        lat, lon
+    );
+    Ok(serde_json::from_str(&http::get(&url)?.into_string()?)?)
+}
+
+impl FireWeather {
+    /// Reads a `get_fire_weather` response
+    pub fn from_open_meteo(json: &Value) -> FireWeather {
+        let current = &json["current"];
+        let hourly = &json["hourly"];
+        let times: Vec<&str> = hourly["time"].as_array().into_iter().flatten().filter_map(Value::as_str).collect();
+        let series = |field: &str| -> Vec<f64> {
+            hourly[field].as_array().into_iter().flatten().map(|v| v.as_f64().unwrap_or(0.0)).collect()
+        };
+        let (humidity, wind_speed, wind_gust, precipitation, cape) = (
+            series("relative_humidity_2m"),
+            series("wind_speed_10m"),
+            series("wind_gusts_10m"),
+            series("precipitation"),
+            series("cape"),
+        );
+
+        // Local "YYYY-MM-DDTHH:MM" times sort lexically, as in the snow report
+        let now = current["time"].as_str().unwrap_or("");
+        let index = times.iter().rposition(|t| *t <= now).unwrap_or(0);
+        let recent_rain = precipitation.get(index.saturating_sub(71)..=index).map_or(0.0, |hours| hours.iter().sum());
+        let next_hours = (index..(index + 24).min(times.len()))
+            .map(|i| Hour {
+                time: times[i].to_string(),
+                humidity: humidity.get(i).copied().unwrap_or(0.0),
+                wind_speed: wind_speed.get(i).copied().unwrap_or(0.0),
+                wind_gust: wind_gust.get(i).copied().unwrap_or(0.0),
+                cape: cape.get(i).copied().unwrap_or(0.0),
+            })
+            .collect();
+
+        let today = now.get(..10).unwrap_or("");
+        let days: Vec<&str> = json["daily"]["time"].as_array().into_iter().flatten().filter_map(Value::as_str).collect();
+        let daily_rain: Vec<f64> =
+            json["daily"]["precipitation_sum"].as_array().into_iter().flatten().map(|v| v.as_f64().unwrap_or(0.0)).collect();
+        let dry_days = days
+            .iter()
+            .zip(&daily_rain)
+            .filter(|(day, _)| **day < today)
+            .rev()
+            .take_while(|(_, rain)| **rain < 1.0)
+            .count();
+
+        FireWeather {
+            temp: current["temperature_2m"].as_f64().unwrap_or(0.0),
+            humidity: current["relative_humidity_2m"].as_f64().unwrap_or(0.0),
+            wind_speed: current["wind_speed_10m"].as_f64().unwrap_or(0.0),
+            wind_deg: current["wind_direction_10m"].as_f64().unwrap_or(0.0),
+            wind_gust: current["wind_gusts_10m"].as_f64(),
+            vapour_pressure_deficit: current["vapour_pressure_deficit"].as_f64(),
+            recent_rain,
+            dry_days,
+            next_hours,
+            lightning: Lightning::Unavailable,
+        }
+    }
+
+    /// The Fosberg Fire Weather Index, 0 to 100, from temperature, humidity
+    /// and wind alone; above 50 fire spreads readily
+    pub fn fosberg(&self) -> f64 {
+        let (t, h) = (self.temp * 9.0 / 5.0 + 32.0, self.humidity);
+        let wind_mph = self.wind_speed * 2.23694;
+        // Equilibrium moisture content of dead fuels, in percent
+        let m = match h {
+            h if h < 10.0 => 0.03229 + 0.281073 * h - 0.000578 * h * t,
+            h if h < 50.0 => 2.22749 + 0.160107 * h - 0.01478 * t,
+            h => 21.0606 + 0.005565 * h * h - 0.00035 * h * t - 0.483199 * h,
+        };
+        let x = m / 30.0;
+        let damping = 1.0 - 2.0 * x + 1.5 * x * x - 0.5 * x * x * x;
+        (damping * (1.0 + wind_mph * wind_mph).sqrt() / 0.3002).clamp(0.0, 100.0)
+    }
+
+    /// Whether fuels have had enough rain lately to hold off critical conditions
+    fn is_wet(&self) -> bool {
+        self.recent_rain >= WETTING_RAIN
+    }
+
+    fn is_critical(&self) -> bool {
+        let gust = self.wind_gust.unwrap_or(0.0);
+        !self.is_wet()
+            && self.humidity <= CRITICAL_HUMIDITY
+            && (self.wind_speed >= CRITICAL_WIND || gust >= CRITICAL_GUST)
+    }
+
+    /// Warnings for critical fire weather now or in the next 24 hours, for
+    /// lightning that struck nearby on dry fuels, and for thunderstorms
+    /// forecast while the air is dry
+    pub fn warnings(&self, units: &Units) -> Vec<String> {
+        let mut warnings = Vec::new();
+        if self.is_critical() {
+            let gusts = self.wind_gust.map(|gust| format!(", gusts {}", units.format_wind(gust))).unwrap_or_default();
+            warnings.push(format!(
+                "Critical fire weather now: humidity {:.0}% with wind {}{}",
+                self.humidity,
+                units.format_wind(self.wind_speed),
+                gusts
+            ));
+        } else if let Some(hour) = self.next_hours.iter().find(|hour| !self.is_wet() && hour.is_critical()) {
+            warnings.push(format!(
+                "Critical fire weather expected from {}: humidity {:.0}% with wind {}, gusts {}",
+                hour.time.get(11..).unwrap_or(&hour.time),
+                hour.humidity,
+                units.format_wind(hour.wind_speed),
+                units.format_wind(hour.wind_gust)
+            ));
+        }
+        if let Lightning::Strikes(strikes) = &self.lightning {
+            if strikes.ground >= STRIKE_WARNING && !self.is_wet() {
+                warnings.push(format!(
+                    "{} lightning strike(s) to ground within {} in the last {} hours on dry fuels, watch for new fires",
+                    strikes.ground,
+                    radius(units),
+                    lightning::HOURS
+                ));
+            }
+        }
+        let stormy_and_dry =
+            self.next_hours.iter().any(|hour| hour.cape >= LIKELY_STORM_CAPE && hour.humidity <= CRITICAL_HUMIDITY + 10.0);
+        if stormy_and_dry {
+            warnings.push("Thunderstorms forecast in dry air (from CAPE, not lightning data), lightning could start fires".to_string());
+        }
+        warnings
+    }
+}
+
+/// The distance strikes are counted within, in whole units
+fn radius(units: &Units) -> String {
+    format!("{:.0} {}", units.distance(lightning::RADIUS), units.distance_symbol())
+}
+
+/// Describes the strikes near the location
+fn describe_lightning(lightning: &Lightning, units: &Units) -> String {
+    match lightning {
+        Lightning::Strikes(strikes) if strikes.count == 0 => format!("none within {}", radius(units)),
+        Lightning::Strikes(strikes) => {
+            let mut text = format!("{} within {} ({} to ground)", strikes.count, radius(units), strikes.ground);
+            if let Some(nearest) = strikes.nearest {
+                text.push_str(&format!(", nearest {:.0} {}", units.distance(nearest), units.distance_symbol()));
+            }
+            if let Some(latest) = strikes.latest {
+                text.push_str(&format!(", latest {} ago", time::format_age(time::now() - latest)));
+            }
+            text
+        }
+        Lightning::NotCovered => "no strike data here, only the Nordic and Baltic countries are covered".to_string(),
+        Lightning::Unavailable => "strike data unavailable".to_string(),
+    }
+}
+
+/// Describes a Fosberg index
+fn fosberg_rating(index: f64) -> &'static str {
+    match index {
+        i if i < 25.0 => "low",
+        i if i < 50.0 => "moderate",
+        i if i < 75.0 => "high",
+        _ => "extreme",
+    }
+}
+
+/// Describes the forecast thunderstorm potential of the highest CAPE expected
+fn storm_potential(cape: f64) -> &'static str {
+    match cape {
+        c if c < 300.0 => "low",
+        c if c < LIKELY_STORM_CAPE => "moderate",
+        c if c < 2500.0 => "high",
+        _ => "very high",
+    }
+}
+
+/// Prints the fire weather report with any warnings under it
+pub fn print_fire_weather(fire: &FireWeather, units: &Units, plain: bool) {
+    let mut rows = vec![
+        ("Humidity", format!("{:.0}%", fire.humidity)),
+        ("Temperature", units.format_temperature(fire.temp)),
+    ];
+    let mut wind = format!("{} {}", units.format_wind(fire.wind_speed), wind::compass(fire.wind_deg));
+    if let Some(gust) = fire.wind_gust {
+        wind = format!("{}, gusts {}", wind, units.format_wind(gust));
+    }
+    rows.push(("Wind", wind));
+    rows.push(("Rain last 3 days", units.format_precipitation(fire.recent_rain)));
+    let dry_days = match fire.dry_days {
+        7 => "7 or more".to_string(),
+        days => days.to_string(),
+    };
+    rows.push(("Days without rain", dry_days));
+    if let Some(deficit) = fire.vapour_pressure_deficit {
+        rows.push(("Vapour pressure deficit", format!("{:.1} kPa", deficit)));
+    }
+    let index = fire.fosberg();
+    rows.push(("Fosberg index", format!("{:.0} ({})", index, fosberg_rating(index))));
+    if let Some(driest) = fire.next_hours.iter().min_by(|a, b| a.humidity.total_cmp(&b.humidity)) {
+        let time = driest.time.get(11..).unwrap_or(&driest.time);
+        rows.push(("Driest next 24h", format!("{:.0}% at {}", driest.humidity, time)));
+    }
+    let lightning_label = format!("Lightning last {}h", lightning::HOURS);
+    rows.push((&lightning_label, describe_lightning(&fire.lightning, units)));
+    let cape = fire.next_hours.iter().map(|hour| hour.cape).fold(0.0, f64::max);
+    rows.push(("Storm potential", format!("{} in the forecast for the next 24h (CAPE {:.0} J/kg)", storm_potential(cape), cape)));
+
+    let width = rows.iter().map(|(label, _)| label.len()).max().unwrap_or(0) + 1;
+    for (label, value) in &rows {
+        match plain {
+            true => println!("{}: {}", label, value),
+            false => println!("{:width$} {}", format!("{}:", label), value),
+        }
+    }
+
+    println!();
+    let warnings = fire.warnings(units);
+    match warnings.is_empty() {
+        true => println!("No critical fire weather expected in the next 24 hours."),
+        false => warnings.iter().for_each(|warning| println!("{}", warning)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lightning::Strikes;
+
+    /// Dry and calm weather, with a quiet day ahead
+    fn quiet() -> FireWeather {
+        FireWeather {
+            temp: 30.0,
+            humidity: 20.0,
+            wind_speed: 2.0,
+            wind_deg: 250.0,
+            wind_gust: Some(4.0),
+            vapour_pressure_deficit: None,
+            recent_rain: 0.0,
+            dry_days: 5,
+            next_hours: Vec::new(),
+            lightning: Lightning::Unavailable,
+        }
+    }
+
+    fn hour(time: &str, humidity: f64, wind_speed: f64, wind_gust: f64, cape: f64) -> Hour {
+        Hour { time: time.to_string(), humidity, wind_speed, wind_gust, cape }
+    }
+
+    fn warnings(fire: &FireWeather) -> Vec<String> {
+        fire.warnings(&Units::preset("metric"))
+    }
+
+    #[test]
+    fn critical_at_the_thresholds() {
+        let fire = FireWeather { humidity: CRITICAL_HUMIDITY, wind_speed: CRITICAL_WIND, ..quiet() };
+        assert_eq!(warnings(&fire), ["Critical fire weather now: humidity 25% with wind 6.7 m/s, gusts 4.0 m/s"]);
+        let fire = FireWeather { humidity: CRITICAL_HUMIDITY, wind_gust: Some(CRITICAL_GUST), ..quiet() };
+        assert!(fire.is_critical());
+
+        assert!(!FireWeather { humidity: CRITICAL_HUMIDITY + 1.0, wind_speed: 10.0, ..quiet() }.is_critical());
+        assert!(!FireWeather { wind_speed: CRITICAL_WIND - 0.1, wind_gust: Some(CRITICAL_GUST - 0.1), ..quiet() }.is_critical());
+        assert!(!FireWeather { wind_speed: 10.0, recent_rain: WETTING_RAIN, ..quiet() }.is_critical());
+    }
+
+    #[test]
+    fn expected_critical_gives_wind_and_gusts() {
+        let fire = FireWeather {
+            next_hours: vec![hour("2026-10-14T13:00", 30.0, 8.0, 14.0, 0.0), hour("2026-10-14T15:00", 18.0, 3.0, 12.0, 0.0)],
+            ..quiet()
+        };
+        assert_eq!(warnings(&fire), ["Critical fire weather expected from 15:00: humidity 18% with wind 3.0 m/s, gusts 12.0 m/s"]);
+        assert!(warnings(&FireWeather { recent_rain: 5.0, ..fire }).is_empty());
+    }
+
+    #[test]
+    fn dry_strikes_to_ground_warn() {
+        let strikes = |ground| Lightning::Strikes(Strikes { count: 3, ground, nearest: Some(2000.0), latest: None });
+        let fire = FireWeather { lightning: strikes(STRIKE_WARNING), ..quiet() };
+        assert_eq!(
+            warnings(&fire),
+            ["1 lightning strike(s) to ground within 50 km in the last 24 hours on dry fuels, watch for new fires"]
+        );
+        assert!(warnings(&FireWeather { lightning: strikes(0), ..quiet() }).is_empty());
+        assert!(warnings(&FireWeather { lightning: strikes(2), recent_rain: WETTING_RAIN, ..quiet() }).is_empty());
+        assert!(warnings(&FireWeather { lightning: Lightning::NotCovered, ..quiet() }).is_empty());
+    }
+
+    #[test]
+    fn thunderstorms_in_dry_air_warn() {
+        let stormy = |humidity, cape| FireWeather { next_hours: vec![hour("2026-10-14T16:00", humidity, 2.0, 4.0, cape)], ..quiet() };
+        assert_eq!(warnings(&stormy(CRITICAL_HUMIDITY + 10.0, LIKELY_STORM_CAPE)).len(), 1);
+        assert!(warnings(&stormy(CRITICAL_HUMIDITY + 11.0, LIKELY_STORM_CAPE)).is_empty());
+        assert!(warnings(&stormy(20.0, LIKELY_STORM_CAPE - 1.0)).is_empty());
+        assert_eq!(storm_potential(LIKELY_STORM_CAPE), "high");
+    }
+
+    #[test]
+    fn fosberg_stays_in_range() {
+        assert!(FireWeather { humidity: 100.0, wind_speed: 0.0, ..quiet() }.fosberg() < 1.0);
+        assert_eq!(FireWeather { humidity: 5.0, wind_speed: 40.0, ..quiet() }.fosberg(), 100.0);
+        let index = FireWeather { humidity: 15.0, wind_speed: 9.0, ..quiet() }.fosberg();
+        assert_eq!(fosberg_rating(index), "high", "{}", index);
+    }
+}
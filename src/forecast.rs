@@ -0,0 +1,90 @@
+use crate::error::WeatherError;
+use crate::http;
+use crate::model::{DailyForecast, Forecast};
+use crate::owm;
+use crate::time::{self, TimeFormat};
+use crate::units::Units;
+use serde_json::Value;
+
+/// Days covered by the free 5 day / 3 hour forecast
+pub const MAX_DAYS: usize = 5;
+
+/// Fetches the 5 day / 3 hour forecast from OpenWeatherMap, in metric
+pub fn get_forecast(api_key: &str, lat: &f64, lon: &f64, lang: &str) -> Result<Value, WeatherError> {
+    let (lat, lon) = (lat.to_string(), lon.to_string());
+    let url = http::with_query(
+        &owm::url("/data/2.5/forecast"),
+        &[("lat", &lat), ("lon", &lon), ("appid", api_key), ("units", "metric"), ("lang", lang)],
+    );
+
+    let response = http::get(&url)?.into_string()?;
+    Ok(serde_json::from_str(&response)?)
+}
+
+/// Print one line per day, or per 3-hour step with `hourly`, as a table or
+/// as plain sentences
+pub fn print_forecast(forecast: &Forecast, days: usize, hourly: bool, units: &Units, time_format: TimeFormat, plain: bool) {
+    println!("Forecast for {}:", forecast.city);
+
+    for day in forecast.daily.iter().take(days) {
+        if plain {
+            print_plain_day(forecast, day, hourly, units, time_format);
+            continue;
+        }
+        println!(
+            "{} {} | {:>8} / {:>8} | {:>3.0}% {:>8} | {}",
+            day.weekday,
+            day.date,
+            units.format_temperature(day.temp.min),
+            units.format_temperature(day.temp.max),
+            day.pop * 100.0,
+            units.format_precipitation(day.precipitation),
+            day.description
+        );
+        if !hourly {
+            continue;
+        }
+
+        let steps = forecast.hourly.iter().filter(|step| time::date(step.time, forecast.utc_offset) == day.date);
+        for step in steps {
+            println!(
+                "    {:>8} | {:>8} | {:>3.0}% {:>8} | {:>10} | {}",
+                time::format_time(step.time, forecast.utc_offset, time_format),
+                units.format_temperature(step.temp),
+                step.pop * 100.0,
+                units.format_precipitation(step.precipitation),
+                units.format_wind(step.wind_speed),
+                step.description
+            );
+        }
+    }
+}
+
+/// One day's "Label: value" line, and its steps with `hourly`
+fn print_plain_day(forecast: &Forecast, day: &DailyForecast, hourly: bool, units: &Units, time_format: TimeFormat) {
+    println!(
+        "{} {}: low {}, high {}, {:.0}% chance of {}, {}",
+        day.weekday,
+        day.date,
+        units.format_temperature(day.temp.min),
+        units.format_temperature(day.temp.max),
+        day.pop * 100.0,
+        units.format_precipitation(day.precipitation),
+        day.description
+    );
+    if !hourly {
+        return;
+    }
+    for step in forecast.hourly.iter().filter(|step| time::date(step.time, forecast.utc_offset) == day.date) {
+        println!(
+            "{} {}: {}, {:.0}% chance of {}, wind {}, {}",
+            day.weekday,
+            time::format_time(step.time, forecast.utc_offset, time_format),
+            units.format_temperature(step.temp),
+            step.pop * 100.0,
+            units.format_precipitation(step.precipitation),
+            units.format_wind(step.wind_speed),
+            step.description
+        );
+    }
+}
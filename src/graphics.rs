@@ -0,0 +1,133 @@
+use crate::radar::Image;
+use base64::Engine;
+use std::collections::HashMap;
+use std::env;
+use std::fmt::Write;
+use std::io::IsTerminal;
+
+/// How images are drawn in the terminal
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq)]
+pub enum Graphics {
+    /// Detect the best protocol the terminal supports
+    Auto,
+    /// Kitty graphics protocol
+    Kitty,
+    /// DEC sixel graphics
+    Sixel,
+    /// Colored block characters, supported everywhere
+    Blocks,
+}
+
+impl Graphics {
+    /// Resolves `Auto` to a concrete renderer for the current terminal
+    pub fn resolve(self) -> Graphics {
+        match self {
+            Graphics::Auto => detect(),
+            other => other,
+        }
+    }
+}
+
+/// Guesses the terminal's image support from its environment, since querying
+/// the terminal directly would require switching it into raw mode.
+fn detect() -> Graphics {
+    if !std::io::stdout().is_terminal() {
+        return Graphics::Blocks;
+    }
+
+    let term = env::var("TERM").unwrap_or_default();
+    let program = env::var("TERM_PROGRAM").unwrap_or_default();
+
+    if env::var_os("KITTY_WINDOW_ID").is_some()
+        || term == "xterm-kitty"
+        || term == "xterm-ghostty"
+        || matches!(program.as_str(), "WezTerm" | "ghostty")
+    {
+        Graphics::Kitty
+    } else if term.contains("sixel")
+        || term.starts_with("foot")
+        || term.starts_with("mlterm")
+        || term.starts_with("contour")
+        || matches!(program.as_str(), "iTerm.app" | "mintty")
+    {
+        Graphics::Sixel
+    } else {
+        Graphics::Blocks
+    }
+}
+
+/// Encodes a PNG as a kitty graphics protocol escape sequence, scaled to the
+/// given size in terminal cells.
+pub fn kitty_image(png: &[u8], columns: u32, rows: u32) -> String {
+    let data = base64::engine::general_purpose::STANDARD.encode(png);
+    let chunks: Vec<&[u8]> = data.as_bytes().chunks(4096).collect();
+
+    let mut out = String::new();
+    for (i, chunk) in chunks.iter().enumerate() {
+        let more = u8::from(i + 1 < chunks.len());
+        let chunk = std::str::from_utf8(chunk).unwrap_or_default();
+        if i == 0 {
+            let _ = write!(out, "\x1b_Ga=T,f=100,c={},r={},m={};{}\x1b\\", columns, rows, more, chunk);
+        } else {
+            let _ = write!(out, "\x1b_Gm={};{}\x1b\\", more, chunk);
+        }
+    }
+    out
+}
+
+/// Encodes an image as sixel data, quantized to a 6x6x6 color cube.
+/// Transparent pixels are left unpainted so the terminal background shows.
+pub fn sixel_image(image: &Image) -> String {
+    let cube = |v: u8| (v as u32 * 5 + 127) / 255;
+    let color_index = |x: u32, y: u32| -> Option<u32> {
+        let i = ((y * image.width + x) * 4) as usize;
+        let p = &image.rgba[i..i + 4];
+        (p[3] > 64).then(|| cube(p[0]) * 36 + cube(p[1]) * 6 + cube(p[2]))
+    };
+
+    let mut out = String::from("\x1bP0;1;0q");
+    let _ = write!(out, "\"1;1;{};{}", image.width, image.height);
+    for index in 0..216 {
+        let (r, g, b) = (index / 36, index / 6 % 6, index % 6);
+        let _ = write!(out, "#{};2;{};{};{}", index, r * 20, g * 20, b * 20);
+    }
+
+    for band in (0..image.height).step_by(6) {
+        // Collect the six-pixel column pattern for each color used in this band
+        let mut patterns: HashMap<u32, Vec<u8>> = HashMap::new();
+        for x in 0..image.width {
+            for dy in 0..6.min(image.height - band) {
+                if let Some(index) = color_index(x, band + dy) {
+                    patterns.entry(index).or_insert_with(|| vec![0; image.width as usize])[x as usize] |= 1 << dy;
+                }
+            }
+        }
+
+        let mut indices: Vec<&u32> = patterns.keys().collect();
+        indices.sort();
+        for index in indices {
+            let _ = write!(out, "#{}", index);
+            encode_runs(&mut out, &patterns[index]);
+            out.push('$');
+        }
+        out.push('-');
+    }
+
+    out.push_str("\x1b\\");
+    out
+}
+
+/// Writes sixel characters, run-length encoding repeats
+fn encode_runs(out: &mut String, pattern: &[u8]) {
+    let mut i = 0;
+    while i < pattern.len() {
+        let run = pattern[i..].iter().take_while(|p| **p == pattern[i]).count();
+        let sixel = (pattern[i] + 63) as char;
+        if run > 3 {
+            let _ = write!(out, "!{}{}", run, sixel);
+        } else {
+            out.extend(std::iter::repeat_n(sixel, run));
+        }
+        i += run;
+    }
+}
@@ -0,0 +1,72 @@
+use crate::model::CurrentWeather;
+use directories::ProjectDirs;
+use std::error::Error;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// File formats for the observation log
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq)]
+pub enum LogFormat {
+    /// One JSON object per line, with every model field
+    Jsonl,
+    /// Spreadsheet-friendly columns with a header row
+    Csv,
+}
+
+/// Column names of the CSV rows
+pub const CSV_HEADER: &str = "updated,city,condition,temp,humidity,wind_speed,wind_deg,pressure";
+
+/// Default log file in the data directory, named after the format
+pub fn default_log_path(format: LogFormat) -> Option<PathBuf> {
+    let name = match format {
+        LogFormat::Jsonl => "weather_log.jsonl",
+        LogFormat::Csv => "weather_log.csv",
+    };
+    ProjectDirs::from("", "", "rusty_weather").map(|dirs| dirs.data_dir().join(name))
+}
+
+/// Appends one observation, in metric units so the log stays comparable
+/// when display units change
+pub fn append_observation(weather: &CurrentWeather, path: &Path, format: LogFormat) -> Result<(), Box<dyn Error>> {
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        fs::create_dir_all(dir)?;
+    }
+    let is_new = fs::metadata(path).map_or(true, |meta| meta.len() == 0);
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+
+    match format {
+        LogFormat::Jsonl => writeln!(file, "{}", serde_json::to_string(weather)?)?,
+        LogFormat::Csv => {
+            if is_new {
+                writeln!(file, "{}", CSV_HEADER)?;
+            }
+            writeln!(file, "{}", csv_row(weather))?;
+        }
+    }
+    Ok(())
+}
+
+/// An observation as a CSV row, in the order of `CSV_HEADER`
+pub fn csv_row(weather: &CurrentWeather) -> String {
+    format!(
+        "{},{},{},{},{},{},{},{}",
+        weather.updated,
+        csv_field(&weather.city),
+        csv_field(&weather.condition),
+        weather.temp,
+        weather.humidity,
+        weather.wind.speed,
+        weather.wind.deg,
+        weather.pressure
+    )
+}
+
+/// Quotes a CSV field when it contains a separator or quote
+pub fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
@@ -0,0 +1,402 @@
+use crate::quota;
+use std::env;
+use std::error::Error;
+use std::fmt;
+use std::io::Read;
+use std::panic;
+use std::sync::OnceLock;
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tracing::{debug, info, warn};
+
+/// Timeouts, retry policy and proxy for all HTTP requests
+#[derive(Debug, Clone)]
+pub struct HttpSettings {
+    pub connect_timeout: Duration,
+    pub read_timeout: Duration,
+    /// Extra attempts after a transient failure
+    pub retries: u32,
+    /// Proxy for all requests, e.g. "http://proxy:3128" or "socks5://localhost:1080".
+    /// Without one, the usual proxy environment variables are used.
+    pub proxy: Option<String>,
+}
+
+impl Default for HttpSettings {
+    fn default() -> Self {
+        HttpSettings {
+            connect_timeout: Duration::from_secs(5),
+            read_timeout: Duration::from_secs(15),
+            retries: 3,
+            proxy: None,
+        }
+    }
+}
+
+/// First retry delay, doubled on each further attempt
+const BASE_BACKOFF: Duration = Duration::from_millis(500);
+
+/// Longest delay between attempts, including any Retry-After from the server
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+
+/// A successful response
+#[derive(Debug, Clone)]
+pub struct Response {
+    pub status: u16,
+    pub body: Vec<u8>,
+}
+
+impl Response {
+    pub fn into_string(self) -> Result<String, std::string::FromUtf8Error> {
+        String::from_utf8(self.body)
+    }
+}
+
+/// Why a request failed
+#[derive(Debug, Clone)]
+pub enum HttpError {
+    /// The server answered with a non-success status
+    Status { status: u16, body: String },
+    /// The request didn't get an answer, e.g. a DNS failure or timeout
+    Transport(String),
+}
+
+impl fmt::Display for HttpError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            HttpError::Status { status, body } => {
+                // OpenWeatherMap puts a readable explanation in a "message" field
+                let message = serde_json::from_str::<serde_json::Value>(body)
+                    .ok()
+                    .and_then(|json| json["message"].as_str().map(str::to_string));
+                match message {
+                    Some(message) => write!(f, "HTTP {}: {}", status, message),
+                    None => write!(f, "HTTP {}", status),
+                }
+            }
+            HttpError::Transport(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl Error for HttpError {}
+
+/// Something that can answer GET requests. The live implementation goes over
+/// the network; the record/replay ones save and serve fixture files.
+pub trait Transport: Send + Sync {
+    fn get(&self, url: &str) -> Result<Response, HttpError>;
+
+    /// Sends a body, e.g. points to a metrics database
+    fn post(&self, url: &str, headers: &[(&str, &str)], body: &[u8]) -> Result<Response, HttpError>;
+}
+
+static TRANSPORT: OnceLock<Box<dyn Transport>> = OnceLock::new();
+
+/// Sets up the live HTTP transport. Requests made before this use defaults.
+pub fn init(settings: HttpSettings) -> Result<(), Box<dyn Error>> {
+    set_transport(Box::new(UreqTransport::new(settings)?));
+    Ok(())
+}
+
+/// Replaces the transport used for all requests, see `init`
+pub fn set_transport(transport: Box<dyn Transport>) {
+    let _ = TRANSPORT.set(transport);
+}
+
+/// Creates the live transport on its own, for wrapping in another one
+pub fn live_transport(settings: HttpSettings) -> Result<Box<dyn Transport>, Box<dyn Error>> {
+    Ok(Box::new(UreqTransport::new(settings)?))
+}
+
+fn transport() -> &'static dyn Transport {
+    // init() reports bad proxy settings; if it was skipped, go without a proxy
+    TRANSPORT
+        .get_or_init(|| {
+            Box::new(UreqTransport::new(HttpSettings::default()).unwrap_or_else(|_| UreqTransport {
+                direct: ureq::agent(),
+                http: None,
+                https: None,
+                no_proxy: Vec::new(),
+                settings: HttpSettings::default(),
+            }))
+        })
+        .as_ref()
+}
+
+/// Sends a GET request through the configured transport
+pub fn get(url: &str) -> Result<Response, HttpError> {
+    transport().get(url)
+}
+
+/// Sends a POST request through the configured transport
+pub fn post(url: &str, headers: &[(&str, &str)], body: &[u8]) -> Result<Response, HttpError> {
+    transport().post(url, headers, body)
+}
+
+/// Runs two independent fetches at the same time, so a run that needs both
+/// waits for the slower one rather than their sum
+pub fn join<A: Send, B: Send>(a: impl FnOnce() -> A + Send, b: impl FnOnce() -> B + Send) -> (A, B) {
+    thread::scope(|scope| {
+        let b = scope.spawn(b);
+        let a = a();
+        (a, b.join().unwrap_or_else(|panic| panic::resume_unwind(panic)))
+    })
+}
+
+/// Fetches several URLs at once, returning the results in the same order
+pub fn get_all(urls: &[String]) -> Vec<Result<Response, HttpError>> {
+    thread::scope(|scope| {
+        let handles: Vec<_> = urls.iter().map(|url| scope.spawn(move || get(url))).collect();
+        handles
+            .into_iter()
+            .map(|handle| handle.join().unwrap_or_else(|panic| panic::resume_unwind(panic)))
+            .collect()
+    })
+}
+
+/// Appends query parameters to a URL, percent-encoding the values
+pub fn with_query(url: &str, params: &[(&str, &str)]) -> String {
+    let mut url = url.to_string();
+    for (i, (key, value)) in params.iter().enumerate() {
+        let separator = if i == 0 && !url.contains('?') { '?' } else { '&' };
+        url.push(separator);
+        url.push_str(key);
+        url.push('=');
+        for byte in value.bytes() {
+            match byte {
+                b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => url.push(byte as char),
+                _ => url.push_str(&format!("%{:02X}", byte)),
+            }
+        }
+    }
+    url
+}
+
+/// Hides API keys in a URL or message, so it can be shown or saved
+pub fn redact(text: &str) -> String {
+    let mut redacted = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = ["appid=", "apiKey=", "api_key="]
+        .iter()
+        .filter_map(|param| rest.find(param).map(|i| i + param.len()))
+        .min()
+    {
+        redacted.push_str(&rest[..start]);
+        redacted.push_str("REDACTED");
+        rest = &rest[start..];
+        let end = rest.find(['&', ' ', '#', '"']).unwrap_or(rest.len());
+        rest = &rest[end..];
+    }
+    redacted.push_str(rest);
+    redacted
+}
+
+/// Live transport built on ureq, with retries, quota tracking and proxies.
+/// It lives for the whole run and ureq agents keep connections alive, so
+/// requests to the same host share one connection.
+struct UreqTransport {
+    direct: ureq::Agent,
+    /// Agents for http:// and https:// URLs, when a proxy applies to them
+    http: Option<ureq::Agent>,
+    https: Option<ureq::Agent>,
+    no_proxy: Vec<String>,
+    settings: HttpSettings,
+}
+
+impl UreqTransport {
+    fn new(settings: HttpSettings) -> Result<UreqTransport, Box<dyn Error>> {
+        let agent = |proxy: Option<&str>| -> Result<ureq::Agent, Box<dyn Error>> {
+            let mut builder = ureq::AgentBuilder::new()
+                .timeout_connect(settings.connect_timeout)
+                .timeout_read(settings.read_timeout);
+            if let Some(proxy) = proxy {
+                let parsed = ureq::Proxy::new(proxy).map_err(|e| format!("Invalid proxy \"{}\": {}", proxy, e))?;
+                builder = builder.proxy(parsed);
+            }
+            Ok(builder.build())
+        };
+
+        // A configured proxy wins over the environment, which follows curl's conventions
+        let all = settings.proxy.clone().or_else(|| env_var(&["ALL_PROXY", "all_proxy"]));
+        let http_proxy = env_var(&["http_proxy", "HTTP_PROXY"]);
+        let https_proxy = env_var(&["https_proxy", "HTTPS_PROXY"]);
+        let (http_proxy, https_proxy) = match settings.proxy {
+            Some(_) => (all.clone(), all),
+            None => (http_proxy.or(all.clone()), https_proxy.or(all)),
+        };
+
+        let no_proxy = env_var(&["no_proxy", "NO_PROXY"])
+            .map(|hosts| {
+                hosts
+                    .split(',')
+                    .map(|host| host.trim().trim_start_matches('.').to_lowercase())
+                    .filter(|host| !host.is_empty())
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        Ok(UreqTransport {
+            direct: agent(None)?,
+            http: http_proxy.as_deref().map(|proxy| agent(Some(proxy))).transpose()?,
+            https: https_proxy.as_deref().map(|proxy| agent(Some(proxy))).transpose()?,
+            no_proxy,
+            settings,
+        })
+    }
+
+    /// Picks the agent for a URL based on its scheme and the NO_PROXY list
+    fn agent(&self, url: &str) -> &ureq::Agent {
+        let (scheme, rest) = url.split_once("://").unwrap_or(("http", url));
+        let host = rest.split(['/', '?', '#']).next().unwrap_or("");
+        let host = host.rsplit_once('@').map_or(host, |(_, host)| host);
+        let host = host.split(':').next().unwrap_or(host).to_lowercase();
+
+        let bypass = self.no_proxy.iter().any(|entry| {
+            entry == "*" || host == *entry || host.ends_with(&format!(".{}", entry))
+        });
+        let proxied = match scheme {
+            "https" => self.https.as_ref(),
+            _ => self.http.as_ref(),
+        };
+        match proxied {
+            Some(agent) if !bypass => agent,
+            _ => &self.direct,
+        }
+    }
+}
+
+fn env_var(names: &[&str]) -> Option<String> {
+    names
+        .iter()
+        .filter_map(|name| env::var(name).ok())
+        .find(|value| !value.is_empty())
+}
+
+impl Transport for UreqTransport {
+    fn get(&self, url: &str) -> Result<Response, HttpError> {
+        self.send(self.agent(url).get(url), None)
+    }
+
+    fn post(&self, url: &str, headers: &[(&str, &str)], body: &[u8]) -> Result<Response, HttpError> {
+        let request = headers.iter().fold(self.agent(url).post(url), |request, (name, value)| request.set(name, value));
+        self.send(request, Some(body))
+    }
+}
+
+impl UreqTransport {
+    /// Sends a request, retrying timeouts, connection failures, 429s and 5xx
+    /// responses with exponential backoff and jitter.
+    fn send(&self, request: ureq::Request, body: Option<&[u8]>) -> Result<Response, HttpError> {
+        let (url, method) = (&request.url().to_string(), request.method().to_string());
+        let mut attempt = 0;
+
+        loop {
+            quota::before_call(url);
+            let start = Instant::now();
+            let result = match body {
+                Some(body) => request.clone().send_bytes(body),
+                None => request.clone().call(),
+            };
+            let elapsed = start.elapsed().as_millis();
+            let error = match result {
+                Ok(response) => {
+                    info!(url = %redact(url), status = response.status(), elapsed_ms = elapsed, "{}", method);
+                    let response = read_response(response)?;
+                    debug!(body = %String::from_utf8_lossy(&response.body), "response");
+                    return Ok(response);
+                }
+                Err(error) => {
+                    let status = match &error {
+                        ureq::Error::Status(status, _) => status.to_string(),
+                        ureq::Error::Transport(transport) => transport.kind().to_string(),
+                    };
+                    info!(url = %redact(url), status, elapsed_ms = elapsed, "{} failed", method);
+                    error
+                }
+            };
+            if attempt >= self.settings.retries || !is_transient(&error) {
+                return Err(match error {
+                    ureq::Error::Status(status, response) => HttpError::Status {
+                        status,
+                        body: response.into_string().unwrap_or_default(),
+                    },
+                    ureq::Error::Transport(transport) => HttpError::Transport(redact(&transport.to_string())),
+                });
+            }
+
+            let backoff = BASE_BACKOFF.saturating_mul(1 << attempt.min(16));
+            let delay = retry_after(&error).unwrap_or_else(|| jitter(backoff)).min(MAX_BACKOFF);
+            warn!(url = %redact(url), attempt = attempt + 1, delay_ms = delay.as_millis(), "retrying");
+            thread::sleep(delay);
+            attempt += 1;
+        }
+    }
+}
+
+fn read_response(response: ureq::Response) -> Result<Response, HttpError> {
+    let status = response.status();
+    let mut body = Vec::new();
+    response
+        .into_reader()
+        .read_to_end(&mut body)
+        .map_err(|e| HttpError::Transport(format!("Failed to read response: {}", e)))?;
+    Ok(Response { status, body })
+}
+
+fn is_transient(error: &ureq::Error) -> bool {
+    match error {
+        ureq::Error::Status(status, _) => *status == 429 || (500..600).contains(status),
+        ureq::Error::Transport(transport) => matches!(
+            transport.kind(),
+            ureq::ErrorKind::Dns | ureq::ErrorKind::ConnectionFailed | ureq::ErrorKind::Io
+        ),
+    }
+}
+
+/// Delay requested by the server with a Retry-After header, in seconds
+fn retry_after(error: &ureq::Error) -> Option<Duration> {
+    match error {
+        ureq::Error::Status(_, response) => response
+            .header("Retry-After")
+            .and_then(|seconds| seconds.trim().parse().ok())
+            .map(Duration::from_secs),
+        _ => None,
+    }
+}
+
+/// Randomizes a delay to between half and all of it, so clients that failed
+/// together don't all retry at the same moment
+fn jitter(delay: Duration) -> Duration {
+    let nanos = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.subsec_nanos()).unwrap_or(0);
+    let fraction = 0.5 + (nanos % 1000) as f64 / 2000.0;
+    delay.mul_f64(fraction)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn redacts_each_kind_of_key() {
+        assert_eq!(
+            redact("https://api.openweathermap.org/data/2.5/weather?lat=1&lon=2&appid=secret&units=metric"),
+            "https://api.openweathermap.org/data/2.5/weather?lat=1&lon=2&appid=REDACTED&units=metric"
+        );
+        assert_eq!(redact("https://api.weather.com/v2/pws?apiKey=secret"), "https://api.weather.com/v2/pws?apiKey=REDACTED");
+        assert_eq!(redact("https://example.com/?api_key=secret#top"), "https://example.com/?api_key=REDACTED#top");
+    }
+
+    #[test]
+    fn redacts_every_key_in_a_message() {
+        assert_eq!(
+            redact("GET https://a.example/?appid=one failed, then https://b.example/?x=1&api_key=two&y=2"),
+            "GET https://a.example/?appid=REDACTED failed, then https://b.example/?x=1&api_key=REDACTED&y=2"
+        );
+        assert_eq!(redact("\"url\":\"https://a.example/?apiKey=one\""), "\"url\":\"https://a.example/?apiKey=REDACTED\"");
+        assert_eq!(redact("no keys here"), "no keys here");
+    }
+
+    #[test]
+    fn with_query_encodes_values() {
+        assert_eq!(with_query("https://a.example/wfs", &[("bbox", "1.5,2"), ("q", "São Paulo")]), "https://a.example/wfs?bbox=1.5%2C2&q=S%C3%A3o%20Paulo");
+    }
+}
@@ -0,0 +1,64 @@
+/// Labels used in the weather display
+#[derive(Debug, Clone, Copy)]
+pub enum Label {
+    Temperature,
+    Min,
+    Max,
+    WindSpeed,
+    Pressure,
+    Conditions,
+    Sunrise,
+    Sunset,
+    Updated,
+    FeelsLike,
+    Humidity,
+    DewPoint,
+    Visibility,
+    CloudCover,
+    Pollen,
+}
+
+/// Translated labels per language, in `Label` order
+const LABELS: &[(&str, [&str; 15])] = &[
+    ("en", ["Temperature", "Min", "Max", "Wind Speed", "Pressure", "Conditions", "Sunrise", "Sunset", "Updated", "Feels Like", "Humidity", "Dew Point", "Visibility", "Cloud Cover", "Pollen"]),
+    ("de", ["Temperatur", "Min", "Max", "Windgeschwindigkeit", "Luftdruck", "Wetterlage", "Sonnenaufgang", "Sonnenuntergang", "Aktualisiert", "Gefühlt", "Luftfeuchtigkeit", "Taupunkt", "Sichtweite", "Bewölkung", "Pollen"]),
+    ("es", ["Temperatura", "Mín", "Máx", "Velocidad del viento", "Presión", "Condiciones", "Amanecer", "Atardecer", "Actualizado", "Sensación térmica", "Humedad", "Punto de rocío", "Visibilidad", "Nubosidad", "Polen"]),
+    ("fr", ["Température", "Min", "Max", "Vitesse du vent", "Pression", "Conditions", "Lever du soleil", "Coucher du soleil", "Mis à jour", "Ressenti", "Humidité", "Point de rosée", "Visibilité", "Nébulosité", "Pollen"]),
+    ("it", ["Temperatura", "Min", "Max", "Velocità del vento", "Pressione", "Condizioni", "Alba", "Tramonto", "Aggiornato", "Percepita", "Umidità", "Punto di rugiada", "Visibilità", "Nuvolosità", "Polline"]),
+    ("nl", ["Temperatuur", "Min", "Max", "Windsnelheid", "Luchtdruk", "Omstandigheden", "Zonsopgang", "Zonsondergang", "Bijgewerkt", "Gevoelstemperatuur", "Luchtvochtigheid", "Dauwpunt", "Zicht", "Bewolking", "Pollen"]),
+    ("pl", ["Temperatura", "Min", "Maks", "Prędkość wiatru", "Ciśnienie", "Warunki", "Wschód słońca", "Zachód słońca", "Zaktualizowano", "Odczuwalna", "Wilgotność", "Punkt rosy", "Widoczność", "Zachmurzenie", "Pyłki"]),
+    ("pt", ["Temperatura", "Mín", "Máx", "Velocidade do vento", "Pressão", "Condições", "Nascer do sol", "Pôr do sol", "Atualizado", "Sensação térmica", "Umidade", "Ponto de orvalho", "Visibilidade", "Nebulosidade", "Pólen"]),
+    ("sv", ["Temperatur", "Min", "Max", "Vindhastighet", "Lufttryck", "Förhållanden", "Soluppgång", "Solnedgång", "Uppdaterad", "Känns som", "Luftfuktighet", "Daggpunkt", "Sikt", "Molnighet", "Pollen"]),
+];
+
+/// Returns a label in the given language, falling back to English. Regional
+/// variants like "pt_br" use the base language's labels.
+pub fn label(lang: &str, label: Label) -> &'static str {
+    let base = lang.split(['_', '-']).next().unwrap_or(lang);
+    let labels = LABELS
+        .iter()
+        .find(|(code, _)| code.eq_ignore_ascii_case(base))
+        .unwrap_or(&LABELS[0]);
+    labels.1[label as usize]
+}
+
+/// Reads the language from the system locale in the form OpenWeatherMap
+/// expects, e.g. "de" from "de_DE.UTF-8" or "pt_br" from "pt_BR.UTF-8"
+pub fn locale_language() -> Option<String> {
+    let name = system_locale()?.to_lowercase();
+
+    match name.as_str() {
+        "c" | "posix" => None,
+        "pt_br" | "zh_cn" | "zh_tw" => Some(name),
+        _ => name.split('_').next().map(str::to_string),
+    }
+}
+
+/// Returns the system locale name without encoding or modifier, e.g. "en_GB"
+pub fn system_locale() -> Option<String> {
+    let locale = ["LC_ALL", "LC_MESSAGES", "LANG"]
+        .iter()
+        .filter_map(|var| std::env::var(var).ok())
+        .find(|locale| !locale.is_empty())?;
+    locale.split(['.', '@']).next().map(str::to_string)
+}
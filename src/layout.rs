@@ -0,0 +1,86 @@
+use std::io::{self, IsTerminal};
+use terminal_size::{terminal_size_of, Width};
+
+/// Below this many columns the card drops the art and goes single-column
+const COMPACT_BELOW: usize = 48;
+/// From this many columns the forecast fits beside the card
+const WIDE_FROM: usize = 110;
+
+/// How the weather card is arranged for the space available
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Layout {
+    /// One column of "Label: value" rows under the city, for narrow panes
+    Compact,
+    /// The art and city beside the rows
+    Normal,
+    /// The normal card with forecast columns beside it
+    Wide,
+}
+
+/// Width of the terminal stdout is on, or $COLUMNS when it's redirected
+/// and that's set
+pub fn terminal_width() -> Option<usize> {
+    let stdout = io::stdout();
+    if stdout.is_terminal() {
+        if let Some((Width(width), _)) = terminal_size_of(&stdout) {
+            return Some(width as usize);
+        }
+    }
+    std::env::var("COLUMNS").ok()?.parse().ok()
+}
+
+/// Picks the layout for a terminal width, keeping the normal one when the
+/// width isn't known so piped output stays the same
+pub fn for_width(width: Option<usize>) -> Layout {
+    match width {
+        Some(width) if width < COMPACT_BELOW => Layout::Compact,
+        Some(width) if width >= WIDE_FROM => Layout::Wide,
+        _ => Layout::Normal,
+    }
+}
+
+/// Number of characters text takes up on screen, leaving out color escapes
+pub fn width(text: &str) -> usize {
+    strip_colors(text).chars().count()
+}
+
+/// Cuts text to at most `width` characters, ending in an ellipsis when cut.
+/// Text that has to be cut loses its colors.
+pub fn truncate(text: &str, width: usize) -> String {
+    if self::width(text) <= width {
+        return text.to_string();
+    }
+    let kept: String = strip_colors(text).chars().take(width.saturating_sub(1)).collect();
+    format!("{}…", kept)
+}
+
+/// Breaks text into lines of at most `width` characters at spaces, cutting
+/// words that are longer than a line
+pub fn wrap(text: &str, width: usize) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    for word in text.split_whitespace() {
+        match lines.last_mut() {
+            Some(line) if line.chars().count() + 1 + word.chars().count() <= width => {
+                line.push(' ');
+                line.push_str(word);
+            }
+            _ => lines.push(truncate(word, width)),
+        }
+    }
+    lines
+}
+
+/// Text without its ANSI color escapes, like "\x1b[31m"
+fn strip_colors(text: &str) -> String {
+    let mut plain = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\x1b' => {
+                chars.by_ref().find(|&c| c == 'm');
+            }
+            c => plain.push(c),
+        }
+    }
+    plain
+}
@@ -0,0 +1,184 @@
+use crate::error::WeatherError;
+use crate::{http, location, time};
+use std::collections::BTreeMap;
+
+/// Open data service of the Finnish Meteorological Institute, whose
+/// lightning network reports every located strike and needs no API key
+const FMI_WFS_URL: &str = "https://opendata.fmi.fi/wfs";
+const STORED_QUERY: &str = "fmi::observations::lightning::simple";
+
+/// Rough area the FMI network locates strikes in: the Nordic and Baltic
+/// countries, as south, north, west and east bounds in degrees
+const COVERAGE: (f64, f64, f64, f64) = (54.0, 71.5, 4.0, 36.0);
+
+/// Strikes this close, in meters, count as nearby; about 30 miles
+pub const RADIUS: f64 = 50_000.0;
+/// Hours back that strikes are counted
+pub const HOURS: i64 = 24;
+
+const METERS_PER_DEGREE: f64 = 111_320.0;
+
+/// Lightning strikes within `RADIUS` over the last `HOURS`
+#[derive(Debug, Default, PartialEq)]
+pub struct Strikes {
+    pub count: usize,
+    /// Cloud-to-ground strikes, the ones that start fires
+    pub ground: usize,
+    /// Distance of the closest strike, in meters
+    pub nearest: Option<f64>,
+    /// Time of the latest strike
+    pub latest: Option<i64>,
+}
+
+/// What's known about lightning near a location
+pub enum Lightning {
+    Strikes(Strikes),
+    /// The location is outside the strike network
+    NotCovered,
+    /// The strike data couldn't be fetched
+    Unavailable,
+}
+
+/// Whether strikes are located at these coordinates
+pub fn covers(lat: f64, lon: f64) -> bool {
+    let (south, north, west, east) = COVERAGE;
+    (south..=north).contains(&lat) && (west..=east).contains(&lon)
+}
+
+/// Fetches the strikes in a box around the coordinates, from the start of
+/// the hour `HOURS` ago so the request stays the same for an hour
+pub fn get_strikes(lat: f64, lon: f64) -> Result<String, WeatherError> {
+    let now = time::now();
+    let start = now - now.rem_euclid(3600) - HOURS * 3600;
+    let (dlat, dlon) = (RADIUS / METERS_PER_DEGREE, RADIUS / METERS_PER_DEGREE / lat.to_radians().cos().max(0.01));
+    let bbox = format!("{:.2},{:.2},{:.2},{:.2}", lon - dlon, lat - dlat, lon + dlon, lat + dlat);
+    let url = http::with_query(
+        FMI_WFS_URL,
+        &[
+            ("service", "WFS"),
+            ("version", "2.0.0"),
+            ("request", "getFeature"),
+            ("storedquery_id", STORED_QUERY),
+            ("bbox", &bbox),
+            ("starttime", &time::iso_utc(start)),
+        ],
+    );
+    Ok(http::get(&url)?.into_string()?)
+}
+
+impl Strikes {
+    /// Counts the strikes in a `get_strikes` response that are within
+    /// `RADIUS` of the coordinates and no older than `HOURS` before `now`.
+    /// Each strike comes as one element per measured parameter, all with
+    /// the same position and time.
+    pub fn from_fmi(xml: &str, lat: f64, lon: f64, now: i64) -> Strikes {
+        // Cloud indicator of each strike, keyed by time and position
+        let mut strikes: BTreeMap<(&str, &str), Option<bool>> = BTreeMap::new();
+        for element in xml.split("<BsWfs:BsWfsElement").skip(1) {
+            let (Some(position), Some(time)) = (tag(element, "gml:pos"), tag(element, "BsWfs:Time")) else {
+                continue;
+            };
+            let cloud = strikes.entry((time, position)).or_default();
+            if tag(element, "BsWfs:ParameterName") == Some("cloud_indicator") {
+                *cloud = tag(element, "BsWfs:ParameterValue").and_then(|value| value.parse::<f64>().ok()).map(|value| value != 0.0);
+            }
+        }
+
+        let mut found = Strikes::default();
+        for ((time, position), cloud) in strikes {
+            let mut coordinates = position.split_whitespace().filter_map(|value| value.parse::<f64>().ok());
+            let (Some(strike_lat), Some(strike_lon), Some(time)) = (coordinates.next(), coordinates.next(), time::parse_utc(time))
+            else {
+                continue;
+            };
+            let distance = location::distance_and_bearing((lat, lon), (strike_lat, strike_lon)).0;
+            if distance > RADIUS || time < now - HOURS * 3600 {
+                continue;
+            }
+            found.count += 1;
+            if cloud == Some(false) {
+                found.ground += 1;
+            }
+            found.nearest = Some(found.nearest.map_or(distance, |nearest| nearest.min(distance)));
+            found.latest = found.latest.max(Some(time));
+        }
+        found
+    }
+}
+
+/// Looks up the strikes near a location where the network covers it. A
+/// failed lookup is logged, since the fire report is useful without it.
+pub fn get_lightning(lat: f64, lon: f64) -> Lightning {
+    if !covers(lat, lon) {
+        return Lightning::NotCovered;
+    }
+    match get_strikes(lat, lon) {
+        Ok(xml) => Lightning::Strikes(Strikes::from_fmi(&xml, lat, lon, time::now())),
+        Err(e) => {
+            tracing::warn!(error = %e, "lightning strikes unavailable");
+            Lightning::Unavailable
+        }
+    }
+}
+
+/// The trimmed text of the first `<name>` element in `xml`
+fn tag<'a>(xml: &'a str, name: &str) -> Option<&'a str> {
+    let start = xml.find(&format!("<{}", name))?;
+    let text = &xml[start..];
+    let text = &text[text.find('>')? + 1..];
+    Some(text[..text.find("</")?].trim())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 2024-10-14T05:33:00Z
+    const NOW: i64 = 1_728_883_980;
+
+    /// The elements of one strike, as the FMI gives them
+    fn strike(time: &str, position: &str, cloud: &str) -> String {
+        ["multiplicity", "cloud_indicator"]
+            .iter()
+            .map(|name| {
+                let value = if *name == "cloud_indicator" { cloud } else { "1" };
+                format!(
+                    "<wfs:member><BsWfs:BsWfsElement gml:id=\"e\"><BsWfs:Location><gml:Point><gml:pos>{} </gml:pos></gml:Point>\
+                     </BsWfs:Location><BsWfs:Time>{}</BsWfs:Time><BsWfs:ParameterName>{}</BsWfs:ParameterName>\
+                     <BsWfs:ParameterValue>{}</BsWfs:ParameterValue></BsWfs:BsWfsElement></wfs:member>\n",
+                    position, time, name, value
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn counts_nearby_recent_strikes_once_each() {
+        let xml = [
+            strike("2024-10-14T05:23:00Z", "61.52 23.82", "0"),
+            strike("2024-10-14T03:00:00Z", "61.60 23.70", "1"),
+            // Too far, and too old
+            strike("2024-10-14T05:00:00Z", "62.50 23.80", "0"),
+            strike("2024-10-13T05:00:00Z", "61.50 23.80", "0"),
+        ]
+        .concat();
+        let strikes = Strikes::from_fmi(&xml, 61.5, 23.8, NOW);
+        assert_eq!((strikes.count, strikes.ground), (2, 1));
+        assert_eq!(strikes.latest, Some(NOW - 600));
+        let nearest = strikes.nearest.unwrap();
+        assert!((2000.0..2500.0).contains(&nearest), "{}", nearest);
+    }
+
+    #[test]
+    fn no_strikes_in_an_empty_response() {
+        assert_eq!(Strikes::from_fmi("<wfs:FeatureCollection/>", 61.5, 23.8, NOW), Strikes::default());
+    }
+
+    #[test]
+    fn covers_the_nordic_and_baltic_countries() {
+        assert!(covers(61.5, 23.8));
+        assert!(covers(59.4, 24.7));
+        assert!(!covers(40.0, -105.0));
+        assert!(!covers(48.9, 2.4));
+    }
+}
@@ -0,0 +1,210 @@
+use crate::i18n;
+use crate::owm;
+use crate::http;
+use serde_json::Value;
+use std::error::Error;
+use std::fmt;
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::TcpStream;
+use std::time::{Duration, Instant};
+
+/// Default address gpsd listens on
+pub const GPSD_ADDRESS: &str = "localhost:2947";
+
+/// How long to wait for gpsd to report a position fix
+const GPSD_TIMEOUT: Duration = Duration::from_secs(10);
+
+const EARTH_RADIUS: f64 = 6_371_000.0;
+
+/// A geocoding match
+#[derive(Debug, Clone)]
+pub struct Place {
+    pub name: String,
+    pub state: Option<String>,
+    pub country: String,
+    pub lat: f64,
+    pub lon: f64,
+}
+
+impl Place {
+    fn from_json(json: &Value) -> Option<Place> {
+        Some(Place {
+            name: json["name"].as_str().unwrap_or("Unknown").to_string(),
+            state: json["state"].as_str().map(str::to_string),
+            country: json["country"].as_str().unwrap_or("").to_string(),
+            lat: json["lat"].as_f64()?,
+            lon: json["lon"].as_f64()?,
+        })
+    }
+}
+
+impl Place {
+    /// Name, state and country, e.g. "Springfield, Illinois, US"
+    pub fn label(&self) -> String {
+        match &self.state {
+            Some(state) => format!("{}, {}, {}", self.name, state, self.country),
+            None => format!("{}, {}", self.name, self.country),
+        }
+    }
+}
+
+impl fmt::Display for Place {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} ({:.4}, {:.4})", self.label(), self.lat, self.lon)
+    }
+}
+
+/// Resolves a postal code to coordinates with the geocoding API. The code may
+/// carry its own country ("SW1A 1AA,GB"); otherwise the configured country or
+/// the one from the system locale is used.
+pub fn get_lat_long(zip_code: &str, country: Option<&str>, api_key: &str) -> Result<(f64, f64), Box<dyn Error>> {
+    let place = get_zip_place(zip_code, country, api_key)?;
+    Ok((place.lat, place.lon))
+}
+
+/// The `zip` parameter OpenWeatherMap expects, "CODE,COUNTRY"
+pub fn zip_query(zip_code: &str, country: Option<&str>) -> String {
+    match country.map(str::to_string).or_else(locale_country) {
+        Some(country) if !zip_code.contains(',') => format!("{},{}", zip_code.trim(), country),
+        _ => zip_code.trim().to_string(),
+    }
+}
+
+/// The place a postal code belongs to, looked up as `get_lat_long` does
+pub fn get_zip_place(zip_code: &str, country: Option<&str>, api_key: &str) -> Result<Place, Box<dyn Error>> {
+    let zip = zip_query(zip_code, country);
+    let url = http::with_query(&owm::url("/geo/1.0/zip"), &[("zip", &zip), ("appid", api_key)]);
+    let response = http::get(&url)?.into_string()?;
+    let json: Value = serde_json::from_str(&response)?;
+
+    Place::from_json(&json).ok_or_else(|| "Coordinates not found".into())
+}
+
+/// Searches for places matching a postal code or city name. Postal codes may
+/// match one place per country and city names many, so all candidates are
+/// returned for the caller to choose from.
+pub fn find_places(query: &str, country: Option<&str>, api_key: &str) -> Result<Vec<Place>, Box<dyn Error>> {
+    let url = http::with_query(
+        &owm::url("/geo/1.0/direct"),
+        &[("q", query.trim()), ("limit", "5"), ("appid", api_key)],
+    );
+    let (zip_place, response) = http::join(
+        || {
+            let is_zip = query.chars().any(|c| c.is_ascii_digit());
+            is_zip.then(|| get_zip_place(query, country, api_key).ok()).flatten()
+        },
+        || http::get(&url),
+    );
+
+    let mut places: Vec<Place> = zip_place.into_iter().collect();
+    let response = response?.into_string()?;
+    let json: Value = serde_json::from_str(&response)?;
+
+    for place in json.as_array().into_iter().flatten().filter_map(Place::from_json) {
+        // Geocoding results often repeat a place under slightly different coordinates
+        let duplicate = places.iter().any(|p| {
+            p.name == place.name && p.country == place.country && (p.lat - place.lat).abs() < 0.05 && (p.lon - place.lon).abs() < 0.05
+        });
+        if !duplicate {
+            places.push(place);
+        }
+    }
+
+    if places.is_empty() {
+        return Err(format!("No locations found for \"{}\"", query).into());
+    }
+    Ok(places)
+}
+
+/// Reads the country from the system locale, e.g. "GB" from "en_GB.UTF-8"
+pub fn locale_country() -> Option<String> {
+    let locale = i18n::system_locale()?;
+    let territory = locale.split_once('_')?.1;
+    (territory.len() == 2).then(|| territory.to_uppercase())
+}
+
+/// Parses a latitude in degrees, rejecting values outside -90..=90
+pub fn parse_latitude(value: &str) -> Result<f64, String> {
+    parse_degrees(value, 90.0, "Latitude")
+}
+
+/// Parses a longitude in degrees, rejecting values outside -180..=180
+pub fn parse_longitude(value: &str) -> Result<f64, String> {
+    parse_degrees(value, 180.0, "Longitude")
+}
+
+/// Parses a "LAT,LON" coordinate pair
+pub fn parse_coords(value: &str) -> Result<(f64, f64), String> {
+    let (lat, lon) = value
+        .split_once(',')
+        .ok_or_else(|| format!("Expected coordinates as \"LAT,LON\", got \"{}\"", value))?;
+    Ok((parse_latitude(lat.trim())?, parse_longitude(lon.trim())?))
+}
+
+fn parse_degrees(value: &str, limit: f64, name: &str) -> Result<f64, String> {
+    let degrees: f64 = value
+        .parse()
+        .map_err(|_| format!("{} must be a number, got \"{}\"", name, value))?;
+    if (-limit..=limit).contains(&degrees) {
+        Ok(degrees)
+    } else {
+        Err(format!("{} must be between -{} and {}, got {}", name, limit, limit, degrees))
+    }
+}
+
+/// Looks up approximate coordinates for the machine's public IP address
+pub fn get_ip_location() -> Result<(f64, f64), Box<dyn Error>> {
+    let response = http::get("https://ipinfo.io/json")?.into_string()?;
+    let json: Value = serde_json::from_str(&response)?;
+
+    // Coordinates come back as a single "lat,lon" string
+    let loc = json["loc"].as_str().ok_or("Location not found")?;
+    let (lat, lon) = loc.split_once(',').ok_or("Location not found")?;
+
+    Ok((lat.trim().parse()?, lon.trim().parse()?))
+}
+
+/// Reads the current position fix from a gpsd daemon at `address` (host:port).
+/// Each call opens a fresh connection, so repeated calls follow a moving receiver.
+pub fn get_gpsd_location(address: &str) -> Result<(f64, f64), Box<dyn Error>> {
+    let mut stream = TcpStream::connect(address)?;
+    stream.set_read_timeout(Some(GPSD_TIMEOUT))?;
+    stream.write_all(b"?WATCH={\"enable\":true,\"json\":true};\n")?;
+
+    let started = Instant::now();
+    let mut reader = BufReader::new(stream);
+    let mut line = String::new();
+    while started.elapsed() < GPSD_TIMEOUT {
+        line.clear();
+        // The read timeout is how a gpsd without a fix shows up
+        match reader.read_line(&mut line) {
+            Ok(0) => break,
+            Ok(_) => {}
+            Err(e) if matches!(e.kind(), io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut) => break,
+            Err(e) => return Err(e.into()),
+        }
+
+        // Time-position-velocity reports carry a fix once mode is 2 (2D) or 3 (3D)
+        let Ok(report) = serde_json::from_str::<Value>(&line) else {
+            continue;
+        };
+        if report["class"] == "TPV" && report["mode"].as_u64().unwrap_or(0) >= 2 {
+            if let (Some(lat), Some(lon)) = (report["lat"].as_f64(), report["lon"].as_f64()) {
+                return Ok((lat, lon));
+            }
+        }
+    }
+
+    Err("No position fix from gpsd".into())
+}
+
+/// Great-circle distance in meters and initial bearing in degrees from one
+/// point to another
+pub fn distance_and_bearing(from: (f64, f64), to: (f64, f64)) -> (f64, f64) {
+    let (lat1, lat2) = (from.0.to_radians(), to.0.to_radians());
+    let (dlat, dlon) = (lat2 - lat1, (to.1 - from.1).to_radians());
+    let a = (dlat / 2.0).sin().powi(2) + lat1.cos() * lat2.cos() * (dlon / 2.0).sin().powi(2);
+    let distance = 2.0 * EARTH_RADIUS * a.sqrt().asin();
+    let bearing = (dlon.sin() * lat2.cos()).atan2(lat1.cos() * lat2.sin() - lat1.sin() * lat2.cos() * dlon.cos());
+    (distance, bearing.to_degrees().rem_euclid(360.0))
+}
@@ -1,83 +1,1313 @@
-use std::cmp;
-use std::collections::HashMap;
+use std::cell::Cell;
 use std::error::Error;
-use serde_json::{Value};
-use clap::Parser;
-use serde::Deserialize;
-use std::fs::{File, OpenOptions};
-use std::io::{Read, Write};
-use std::path::Path;
-
-/// Command-line arguments
+use std::io::{self, Write};
+use std::time::Duration;
+use serde_json::Value;
+use clap::{ArgGroup, Args as ClapArgs, CommandFactory, Parser, Subcommand};
+use config::{Config, SavedLocation};
+use error::WeatherError;
+use http::HttpError;
+use model::{Alert, CurrentWeather, Forecast};
+use units::Units;
+use std::path::{Path, PathBuf};
+use std::process::{ExitCode, Stdio};
+
+mod alerts;
+mod batch;
+mod big;
+mod cache;
+mod comfort;
+mod compare;
+mod config;
+mod current;
+mod daemon;
+mod error;
+mod export;
+mod feed;
+mod fire;
+mod forecast;
+mod graphics;
+mod history;
+mod http;
+mod i18n;
+mod layout;
+mod lightning;
+mod location;
+mod logging;
+mod model;
+mod nowcast;
+mod owm;
+mod pollen;
+mod prompt;
+mod provider;
+mod query;
+mod quota;
+mod radar;
+mod recent;
+mod replay;
+mod schedule;
+mod script;
+mod secret;
+mod serve;
+mod snow;
+mod station;
+mod storms;
+mod time;
+mod units;
+mod wind;
+
+/// Command-line arguments. Options marked global work before or after the
+/// subcommand; without one, the current conditions are shown as before.
 #[derive(Parser, Debug)]
-#[command(name = "config_app", about = "Configuration Manager")]
+#[command(name = "rusty_weather", about = "Current weather, forecasts and more in the terminal")]
+#[command(group(ArgGroup::new("place").args(["zip", "city", "here", "gpsd", "coords", "lat", "location", "default"])))]
 struct Args {
     /// If set, prompts the user to create a new config file
     #[arg(short, long)]
     setup: bool,
 
-    #[arg(short, long, value_name = "ZIP")]
+    /// Path to the config file, instead of the one in the user config directory
+    #[arg(short, long, value_name = "PATH", env = "RUSTY_WEATHER_CONFIG", global = true)]
+    config: Option<PathBuf>,
+
+    /// Named profile from the config file to use, or to edit with --setup
+    #[arg(short, long, value_name = "NAME", env = "RUSTY_WEATHER_PROFILE", global = true)]
+    profile: Option<String>,
+
+    #[arg(short, long, value_name = "ZIP", global = true)]
     zip: Option<String>,
+
+    /// City to look up, e.g. "Springfield, IL" or "Paris,FR", taking the best match
+    #[arg(long, value_name = "NAME", global = true)]
+    city: Option<String>,
+
+    /// Uses the approximate location of this machine's public IP address
+    #[arg(long, global = true)]
+    here: bool,
+
+    /// Uses the current position fix from gpsd, optionally at HOST:PORT
+    #[arg(long, value_name = "HOST:PORT", num_args = 0..=1, default_missing_value = location::GPSD_ADDRESS, global = true)]
+    gpsd: Option<String>,
+
+    /// Latitude to use instead of the saved location, in degrees
+    #[arg(long, requires = "lon", allow_negative_numbers = true, value_parser = location::parse_latitude, global = true)]
+    lat: Option<f64>,
+
+    /// Longitude to use instead of the saved location, in degrees
+    #[arg(long, requires = "lat", allow_negative_numbers = true, value_parser = location::parse_longitude, global = true)]
+    lon: Option<f64>,
+
+    /// Coordinates to use instead of the saved location, as "LAT,LON"
+    #[arg(long, value_name = "LAT,LON", allow_hyphen_values = true, value_parser = location::parse_coords, global = true)]
+    coords: Option<(f64, f64)>,
+
+    /// Location saved with `locations add`, e.g. "cabin"
+    #[arg(short, long, value_name = "NAME", global = true)]
+    location: Option<String>,
+
+    /// Goes back to the configured location, forgetting the last --zip or --city lookup
+    #[arg(long, visible_alias = "saved", global = true)]
+    default: bool,
+
+    /// Unit preset to display in for this run, overriding the config
+    #[arg(short, long, value_parser = units::PRESETS, global = true)]
+    units: Option<String>,
+
+    /// Prints plain "Label: value" lines without art, alignment or colors,
+    /// for screen readers and logs
+    #[arg(long, visible_alias = "no-art", global = true)]
+    plain: bool,
+
+    /// Logs requests and timing to stderr; repeat for more detail
+    #[arg(short, long, action = clap::ArgAction::Count, global = true)]
+    verbose: u8,
+
+    /// Logs everything -vv does, including raw API responses
+    #[arg(long, global = true)]
+    debug: bool,
+
+    /// Saves every HTTP request and response of this run to a JSON file
+    #[arg(long, value_name = "PATH", conflicts_with = "replay", global = true)]
+    record: Option<PathBuf>,
+
+    /// Answers HTTP requests from a file saved with --record instead of the network
+    #[arg(long, value_name = "PATH", global = true)]
+    replay: Option<PathBuf>,
+
+    /// Options of `current`, accepted without the subcommand too
+    #[command(flatten)]
+    current: CurrentArgs,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+/// Options for the current conditions display
+#[derive(ClapArgs, Debug, Default)]
+struct CurrentArgs {
+    /// Adds a minute-by-minute precipitation nowcast under the current conditions
+    #[arg(short, long)]
+    nowcast: bool,
+
+    /// Prints the API response as pretty-printed JSON instead of the formatted report
+    #[arg(long, conflicts_with = "nowcast")]
+    raw: bool,
+
+    /// Shows the last weather saved for the location instead of fetching it,
+    /// as also happens when the network is down
+    #[arg(long, conflicts_with = "nowcast")]
+    offline: bool,
+
+    /// Prints a single value of the current weather, e.g. "temp" or "wind.speed",
+    /// in the configured units and without labels
+    #[arg(long, value_name = "PATH", conflicts_with_all = ["raw", "nowcast"])]
+    get: Option<String>,
+
+    /// Evaluates a jq-style filter over the current weather, e.g. ".wind | keys"
+    #[arg(long, value_name = "FILTER", conflicts_with_all = ["raw", "nowcast", "get"])]
+    query: Option<String>,
+
+    /// Shows the temperature in large block figures instead of the card,
+    /// for wall-mounted terminals and dashboards
+    #[arg(long, conflicts_with_all = ["raw", "get", "query", "plain"])]
+    big: bool,
+
+    /// Fetches the current weather for every location in a file, or stdin for
+    /// "-", one ZIP code, saved location, city or "lat,lon" per line
+    #[arg(long, value_name = "FILE", conflicts_with_all = ["nowcast", "raw", "offline", "get", "query", "big"])]
+    batch: Option<PathBuf>,
+
+    /// Sends the current conditions to a metrics database instead of printing them
+    #[arg(long, value_enum, value_name = "SINK", conflicts_with_all = ["nowcast", "raw", "offline", "get", "query", "big", "batch"])]
+    export: Option<export::Sink>,
+
+    /// Record format for --batch [default: jsonl]
+    #[arg(long = "format", value_enum, requires = "batch")]
+    batch_format: Option<history::LogFormat>,
 }
 
-#[derive(Debug, serde::Serialize, Deserialize)]
-struct Config {
-    api_key: String,
-    latitude: f64,
-    longitude: f64,
-    units: String,
+impl CurrentArgs {
+    fn is_set(&self) -> bool {
+        self.nowcast || self.raw || self.offline || self.big || self.get.is_some() || self.query.is_some() || self.batch.is_some() || self.export.is_some()
+    }
 }
 
-fn main() -> Result<(), Box<dyn Error>> {
-    let args: Args = Args::parse();
-    let config_path = "config.yaml";
+/// Options for the forecast
+#[derive(ClapArgs, Debug)]
+struct ForecastArgs {
+    /// Number of days to show
+    #[arg(short, long, default_value_t = forecast::MAX_DAYS, value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..=forecast::MAX_DAYS as u64))]
+    days: usize,
 
-    let mut config: Config = if Path::new(config_path).exists() {
-        let mut file = File::open(config_path)?;
-        let mut contents = String::new();
-        file.read_to_string(&mut contents)?;
-        serde_yaml::from_str(&contents)?
-    } else {
-        println!("Config file not found, creating default...");
-        Config {
-            api_key: "".to_string(),
-            latitude: 0.0,
-            longitude: 0.0,
-            units: "imperial".to_string(),
+    /// Lists every 3-hour step under each day
+    #[arg(long)]
+    hourly: bool,
+
+    /// Prints the API response as pretty-printed JSON instead of the formatted report
+    #[arg(long)]
+    raw: bool,
+
+    /// Evaluates a jq-style filter over the forecast, e.g. ".daily[0].temp.max"
+    #[arg(long, value_name = "FILTER", conflicts_with = "raw")]
+    query: Option<String>,
+
+    /// Prints the daily forecast and active alerts in another format, e.g. as
+    /// a feed to publish
+    #[arg(long, value_enum, value_name = "FORMAT", conflicts_with_all = ["raw", "query", "hourly"])]
+    output: Option<feed::Output>,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Shows the current conditions, the same as running without a subcommand
+    Current(CurrentArgs),
+    /// Shows the 5 day forecast, with daily highs, lows and precipitation
+    Forecast(ForecastArgs),
+    /// Shows active weather alerts for the location
+    Alerts {
+        /// Prints the API response as pretty-printed JSON instead of the alerts
+        #[arg(long)]
+        raw: bool,
+    },
+    /// Shows snow depth, fresh snowfall and the multi-day snow forecast
+    Snow {
+        /// Prints the API response as pretty-printed JSON instead of the report
+        #[arg(long)]
+        raw: bool,
+    },
+    /// Shows fire weather: humidity, wind, recent rain, the Fosberg index
+    /// and forecast thunderstorm potential, with warnings for critical conditions
+    Fire {
+        /// Prints the API response as pretty-printed JSON instead of the report
+        #[arg(long)]
+        raw: bool,
+    },
+    /// Shows tree, grass and weed pollen levels now and for the next days
+    Pollen {
+        /// Prints the API response as pretty-printed JSON instead of the levels
+        #[arg(long)]
+        raw: bool,
+    },
+    /// Shows active hurricanes and tropical storms near the location, with
+    /// their forecast tracks and a map
+    Storms {
+        /// Lists every active storm, not only those near the location
+        #[arg(long)]
+        all: bool,
+        /// Prints the API response as pretty-printed JSON instead of the report
+        #[arg(long)]
+        raw: bool,
+    },
+    /// Renders precipitation radar around the location in the terminal
+    Radar {
+        /// Number of recent radar frames to animate (10 minutes apart, 6 covers the last hour)
+        #[arg(short, long, default_value_t = 1)]
+        frames: usize,
+
+        /// How to draw the radar image; `auto` uses kitty or sixel graphics when supported
+        #[arg(short, long, value_enum, default_value_t = graphics::Graphics::Auto)]
+        graphics: graphics::Graphics,
+    },
+    /// Shows the current weather from every configured provider side by side
+    /// and where they disagree
+    CompareProviders,
+    /// Shows API calls used against the key's per-minute and monthly limits
+    Quota,
+    /// Manages the config file
+    Config {
+        #[command(subcommand)]
+        action: ConfigCommand,
+    },
+    /// Manages saved locations, used with --location
+    Locations {
+        #[command(subcommand)]
+        action: LocationsCommand,
+    },
+    /// Serves the current weather, forecast and alerts as JSON over HTTP
+    Serve {
+        /// Address to listen on
+        #[arg(long, value_name = "HOST:PORT", default_value = serve::DEFAULT_LISTEN)]
+        listen: String,
+
+        /// Seconds to reuse a response before fetching it again
+        #[arg(long, value_name = "SECONDS", default_value_t = 600)]
+        cache: u64,
+    },
+    /// Keeps a status file with the current weather of every configured
+    /// location up to date, for status bars and scripts to read
+    Daemon {
+        /// Usual time between refreshes, e.g. 90s, 10m or 1h; shorter when
+        /// precipitation or alerts are near, longer when stable or on battery
+        #[arg(long, value_name = "INTERVAL", default_value = "10m", value_parser = daemon::parse_interval)]
+        interval: Duration,
+
+        /// File to write, instead of status.json in the runtime or data directory
+        #[arg(long, value_name = "PATH")]
+        status_file: Option<PathBuf>,
+
+        /// Also sends every refresh to a metrics database
+        #[arg(long, value_enum, value_name = "SINK")]
+        export: Option<export::Sink>,
+    },
+    /// Prints a short segment like "☀ 21°C" for shell prompts and status
+    /// bars, from saved weather that's refreshed in the background
+    Prompt {
+        /// Color escapes for where the segment is shown
+        #[arg(long, value_enum, default_value_t = prompt::Color::None)]
+        color: prompt::Color,
+
+        /// Age after which the saved weather is refreshed, e.g. 90s, 10m or 1h
+        #[arg(long, value_name = "AGE", default_value = "10m", value_parser = daemon::parse_interval)]
+        max_age: Duration,
+
+        /// Fetches and saves the weather, as the background refresh does
+        #[arg(long, hide = true)]
+        refresh: bool,
+    },
+    /// Appends the current conditions to a log file, e.g. from cron
+    Log {
+        /// File to append to, instead of weather_log.jsonl or .csv in the data directory
+        #[arg(long, value_name = "PATH")]
+        file: Option<PathBuf>,
+
+        #[arg(long, value_enum, default_value_t = history::LogFormat::Jsonl)]
+        format: history::LogFormat,
+    },
+    /// Prints a shell completion script, e.g. `rusty_weather completions bash > ~/.local/share/bash-completion/completions/rusty_weather`
+    Completions {
+        shell: clap_complete::Shell,
+    },
+}
+
+/// Config file operations
+#[derive(Subcommand, Debug)]
+enum ConfigCommand {
+    /// Prints the config, with the API key redacted
+    Show,
+    /// Prints a single config value, with API keys and tokens redacted
+    Get {
+        /// Field name, e.g. units
+        key: String,
+
+        /// Prints API keys and tokens as they are
+        #[arg(long)]
+        reveal: bool,
+    },
+    /// Changes a single config value
+    Set {
+        /// Field name, e.g. units
+        key: String,
+        /// New value
+        value: String,
+    },
+    /// Checks the config for missing or out of range values
+    Validate,
+    /// Rewrites the config file as TOML or YAML
+    Convert {
+        /// Format to convert to; defaults to the one the file isn't in
+        #[arg(long, value_enum)]
+        to: Option<config::Format>,
+    },
+    /// Writes the whole config as one portable file, to stdout as YAML
+    /// unless PATH is given
+    Export {
+        /// File to write, TOML or YAML by extension
+        path: Option<PathBuf>,
+        /// Leave out API keys and tokens
+        #[arg(long)]
+        no_api_key: bool,
+    },
+    /// Replaces the config with an exported one, keeping a backup of the
+    /// current file. An import without an API key keeps the current one.
+    Import {
+        /// File to import, or - for YAML on stdin
+        path: PathBuf,
+    },
+}
+
+/// Saved location operations
+#[derive(Subcommand, Debug)]
+enum LocationsCommand {
+    /// Lists saved locations
+    List,
+    /// Saves a location under a name. Searches for QUERY, a ZIP code or city,
+    /// or else saves the place given with --zip, --city, --coords, --here or --gpsd.
+    Add {
+        name: String,
+        query: Option<String>,
+    },
+    /// Removes a saved location
+    Remove {
+        name: String,
+    },
+}
+
+/// Why `prompt` refuses location options that need a lookup on every render
+const PROMPT_LOOKUP: &str = "prompt runs on every shell prompt, so it can't look up --city, --here, --gpsd or, with another provider, --zip; save the place with `locations add` and use --location";
+
+fn main() -> ExitCode {
+    let args = Args::parse();
+    if args.command.is_some() && args.current.is_set() {
+        Args::command()
+            .error(
+                clap::error::ErrorKind::ArgumentConflict,
+                "--nowcast, --raw, --offline, --big, --get, --query, --batch and --export only apply to the current conditions, put them after the subcommand",
+            )
+            .exit();
+    }
+    // The place options are global, so clap can't check them against --batch in `current`
+    let batch = match &args.command {
+        None => args.current.batch.is_some(),
+        Some(Command::Current(current)) => current.batch.is_some(),
+        _ => false,
+    };
+    let place = args.zip.is_some() || args.city.is_some() || args.here || args.gpsd.is_some() || args.coords.is_some() || args.lat.is_some() || args.location.is_some();
+    if batch && place {
+        Args::command()
+            .error(clap::error::ErrorKind::ArgumentConflict, "--batch takes its locations from the file, so it can't be combined with a location option")
+            .exit();
+    }
+    // The prompt is drawn on every render, so it only takes places it needn't look up
+    if matches!(args.command, Some(Command::Prompt { .. })) && (args.city.is_some() || args.here || args.gpsd.is_some()) {
+        Args::command().error(clap::error::ErrorKind::ArgumentConflict, PROMPT_LOOKUP).exit();
+    }
+    logging::init(if args.debug { args.verbose.max(2) } else { args.verbose });
+    match run(args) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            ExitCode::from(e.exit_code())
         }
+    }
+}
+
+fn run(args: Args) -> Result<(), WeatherError> {
+    let config_path = match &args.config {
+        Some(path) => path.clone(),
+        None => config::default_config_path().map_err(WeatherError::config)?,
     };
+    if let Some(Command::Completions { shell }) = args.command {
+        print_completions(shell, &config_path);
+        return Ok(());
+    }
+    if let Some(Command::Config { action }) = &args.command {
+        return run_config_command(action, &config_path, args.profile.as_deref()).map_err(WeatherError::config);
+    }
+
+    let mut config = config::load_config(&config_path)
+        .map_err(|e| WeatherError::Config(format!("Couldn't load {}: {}", config_path.display(), e)))?;
+    tracing::debug!(path = %config_path.display(), "loaded config");
+    // The profile and environment apply to everything from here on, down to
+    // the proxy, timeouts and limits. --setup may be about to create the profile.
+    let effective = match &args.profile {
+        Some(name) if args.setup => config.with_profile(name).unwrap_or_else(|_| config.clone()),
+        Some(name) => config.with_profile(name).map_err(WeatherError::config)?,
+        None => config.clone(),
+    }
+    .with_env_overrides()
+    .map_err(WeatherError::config)?;
+    match (&args.record, &args.replay) {
+        (Some(path), _) => {
+            let live = http::live_transport(effective.http_settings()).map_err(WeatherError::config)?;
+            http::set_transport(Box::new(replay::RecordingTransport::new(live, path)));
+        }
+        (_, Some(path)) => http::set_transport(Box::new(replay::ReplayTransport::load(path)?)),
+        _ => http::init(effective.http_settings()).map_err(WeatherError::config)?,
+    }
+    quota::init(effective.quota_limits());
+    owm::init(effective.api_base.clone());
+
+    if let Some(Command::Quota) = args.command {
+        quota::print_quota();
+        return Ok(());
+    }
 
     if args.setup {
         println!("Updating configuration...");
-        update_config(&mut config);
-        save_config(&config, config_path)?;
+        let profile = args.profile.as_deref();
+        let mut updated = match profile {
+            Some(name) => {
+                println!("Editing profile \"{}\", values matching the base config aren't stored in it.", name);
+                config.with_profile(name).unwrap_or_else(|_| config.clone())
+            }
+            None => config.clone(),
+        }
+        .with_keyring_fallback(profile);
+
+        update_config(&mut updated);
+        offer_keyring(&mut updated, profile);
+        match profile {
+            Some(name) => config.set_profile(name, &updated).map_err(WeatherError::config)?,
+            None => config = updated,
+        }
+        config::save_config(&config, &config_path).map_err(WeatherError::config)?;
         println!("Configuration updated successfully.");
         return Ok(());
     }
 
-    if config.api_key.is_empty() {
-        println!("No API key configured, please run --setup.");
-        return Ok(());
+    // Locations are saved to the file as loaded, without profile or env overrides
+    let file_config = config;
+    // Drawing the prompt from the saved weather needs no key, only its refresh does
+    let drawing_prompt = matches!(args.command, Some(Command::Prompt { refresh: false, .. }));
+    let mut config = match drawing_prompt {
+        true => effective,
+        false => effective.with_keyring_fallback(args.profile.as_deref()),
+    };
+    if args.plain {
+        config.plain = Some(true);
+    }
+    if let (Some(script), Some(dir)) = (&config.render_script, config_path.parent()) {
+        config.render_script = Some(dir.join(script));
+    }
+
+    if args.default {
+        recent::forget().map_err(|e| WeatherError::Other(format!("Couldn't forget the last location: {}", e)))?;
+    }
+    if let Some(Command::Locations { action: action @ (LocationsCommand::List | LocationsCommand::Remove { .. }) }) = &args.command {
+        return run_locations_command(action, file_config, &config_path, None);
+    }
+
+    // Another provider stands in for OpenWeatherMap in the current view
+    let provider = config.provider()?;
+    let owm_current = matches!(provider, provider::Provider::OpenWeatherMap);
+    if config.api_key.is_empty() && owm_current && !drawing_prompt {
+        return Err(WeatherError::Config("No API key configured, please run --setup.".to_string()));
     }
 
     let api_key = &config.api_key;
-    let mut lat = config.latitude;
-    let mut lon = config.longitude;
-    let units = &config.units;
-
-    if let Some(zip) = args.zip {
-        match get_lat_long(&zip, &config.api_key) {
-            Ok((lat_from_option, lon_from_option)) => {
-                lat = lat_from_option;
-                lon = lon_from_option;
+    let units = &config.units(args.units.as_deref());
+    let lang = &config.lang();
+    let time_format = config.time_format();
+
+    // The current weather endpoint takes a ZIP itself, so there it isn't looked up first
+    let current_args = match &args.command {
+        None => Some(&args.current),
+        Some(Command::Current(current)) => Some(current),
+        _ => None,
+    };
+    if let Some((path, current)) = current_args.and_then(|current| Some((current.batch.as_ref()?, current))) {
+        return run_batch(path, current.batch_format.unwrap_or(history::LogFormat::Jsonl), &config, units);
+    }
+    if let Some(sink) = current_args.and_then(|current| current.export) {
+        let (lat, lon) = resolve_location(&args, &config)?;
+        let weather = provider::parse_current(&config, &provider::fetch_current(&config, lat, lon)?);
+        let influx = export_target(&config, sink)?;
+        return influx.write(&[influx.line(args.location.as_deref().unwrap_or("default"), &weather)]);
+    }
+    if let (Some(current), Some(zip), true) = (current_args, &args.zip, owm_current) {
+        tracing::info!(zip, "location");
+        return show_current(current, &config, Location::Zip(zip), units);
+    }
+
+    if let Some(Command::Prompt { color, max_age, refresh }) = &args.command {
+        let location = match (&args.zip, owm_current) {
+            (Some(zip), true) => Location::Zip(zip),
+            // Other providers need the ZIP looked up first
+            (Some(_), false) => Args::command().error(clap::error::ErrorKind::ArgumentConflict, PROMPT_LOOKUP).exit(),
+            _ => {
+                let (lat, lon) = resolve_location(&args, &config)?;
+                Location::Coords(lat, lon)
+            }
+        };
+        return show_prompt(&config, location, units, *color, *max_age, *refresh);
+    }
+
+    let (lat, lon) = resolve_location(&args, &config)?;
+    tracing::info!(lat, lon, "location");
+
+    match args.command {
+        None => show_current(&args.current, &config, Location::Coords(lat, lon), units)?,
+        Some(Command::Current(current)) => show_current(&current, &config, Location::Coords(lat, lon), units)?,
+        Some(Command::Forecast(ForecastArgs { output: Some(feed::Output::Rss), .. })) => {
+            print!("{}", forecast_feed(&config, lat, lon, units)?)
+        }
+        Some(Command::Forecast(options)) => {
+            let json = forecast::get_forecast(api_key, &lat, &lon, lang)?;
+            if options.raw {
+                return print_raw(&json);
+            }
+            let forecast = Forecast::from_owm(&json);
+            match &options.query {
+                Some(filter) => print_query(&serde_json::to_value(forecast.in_units(units))?, filter)?,
+                None => forecast::print_forecast(&forecast, options.days, options.hourly, units, time_format, config.plain()),
+            }
+        }
+        Some(Command::Alerts { raw }) => {
+            let json = alerts::get_alerts(api_key, &lat, &lon, lang)?;
+            match raw {
+                true => print_raw(&json)?,
+                false => alerts::print_alerts(&Alert::from_owm(&json), json["timezone_offset"].as_i64().unwrap_or(0), time_format),
+            }
+        }
+        Some(Command::Snow { raw }) => {
+            let json = snow::get_snow_report(&lat, &lon)?;
+            match raw {
+                true => print_raw(&json)?,
+                false => snow::print_snow_report(&json, units),
+            }
+        }
+        Some(Command::Fire { raw: true }) => print_raw(&fire::get_fire_weather(&lat, &lon)?)?,
+        Some(Command::Fire { raw: false }) => {
+            let (json, lightning) = http::join(|| fire::get_fire_weather(&lat, &lon), || lightning::get_lightning(lat, lon));
+            let mut fire = fire::FireWeather::from_open_meteo(&json?);
+            fire.lightning = lightning;
+            fire::print_fire_weather(&fire, units, config.plain());
+        }
+        Some(Command::Pollen { raw }) => {
+            let json = pollen::get_pollen(&lat, &lon)?;
+            match raw {
+                true => print_raw(&json)?,
+                false => pollen::print_pollen(&json, config.plain())?,
+            }
+        }
+        Some(Command::Storms { raw: true, .. }) => print_raw(&storms::get_current_storms()?)?,
+        Some(Command::Storms { all, raw: false }) => {
+            storms::print_storms(&storms::get_active_storms()?, all, lat, lon, units, time_format, config.plain())
+        }
+        Some(Command::Radar { frames, graphics }) => {
+            radar::print_radar(&radar::get_radar_frames(&lat, &lon, frames)?, graphics.resolve())?
+        }
+        Some(Command::CompareProviders) => {
+            let providers = provider::Provider::all(&config);
+            let readings = batch::fetch_all(&providers, |provider| compare::Reading {
+                provider: provider.name(),
+                weather: provider.fetch(&config, lat, lon).map(|json| provider.parse(&json)),
+            });
+            compare::print_comparison(&readings, units, config.plain());
+        }
+        Some(Command::Locations { action }) => {
+            let place = SavedLocation { latitude: lat, longitude: lon, name: None };
+            run_locations_command(&action, file_config, &config_path, Some(place))?
+        }
+        Some(Command::Serve { listen, cache }) => {
+            serve::run(&listen, Duration::from_secs(cache), |path| {
+                let result = match path {
+                    "/" | "/current" => provider::fetch_current(&config, lat, lon)
+                        .and_then(|json| Ok(serde_json::to_value(provider::parse_current(&config, &json).in_units(units))?)),
+                    "/forecast" => forecast::get_forecast(api_key, &lat, &lon, lang)
+                        .and_then(|json| Ok(serde_json::to_value(Forecast::from_owm(&json).in_units(units))?)),
+                    "/alerts" => alerts::get_alerts(api_key, &lat, &lon, lang)
+                        .and_then(|json| Ok(serde_json::to_value(Alert::from_owm(&json))?)),
+                    "/feed" | "/rss" => return Some(forecast_feed(&config, lat, lon, units).map(serve::Body::Rss)),
+                    _ => return None,
+                };
+                Some(result.map(serve::Body::Json))
+            })?
+        }
+        Some(Command::Daemon { interval, ref status_file, export }) => {
+            let influx = export.map(|sink| export_target(&config, sink)).transpose()?;
+            let path = status_file
+                .clone()
+                .or_else(daemon::default_status_path)
+                .ok_or_else(|| WeatherError::Config("Could not determine the data directory, pass --status-file".to_string()))?;
+            // The location the other commands would use, then every saved one
+            let live = args.here || args.gpsd.is_some();
+            let mut places = vec![daemon::Place { name: "default".to_string(), lat, lon, live }];
+            places.extend(config.locations.iter().map(|(name, place)| daemon::Place {
+                name: name.clone(),
+                lat: place.latitude,
+                lon: place.longitude,
+                live: false,
+            }));
+            println!("Writing the weather for {} location(s) to {}, refreshing about every {}s", places.len(), path.display(), interval.as_secs());
+            let alerts_available = Cell::new(true);
+            daemon::run(&places, interval, &path, |place| match place.live {
+                true => {
+                    let (lat, lon) = resolve_location(&args, &config)?;
+                    let moved = daemon::Place { name: place.name.clone(), lat, lon, live: true };
+                    refresh_place(&config, &moved, units, &alerts_available, influx)
+                }
+                false => refresh_place(&config, place, units, &alerts_available, influx),
+            })?
+        }
+        Some(Command::Log { file, format }) => {
+            let json = provider::fetch_current(&config, lat, lon)?;
+            let path = file
+                .or_else(|| history::default_log_path(format))
+                .ok_or_else(|| WeatherError::Config("Could not determine the data directory, pass --file".to_string()))?;
+            history::append_observation(&provider::parse_current(&config, &json), &path, format)
+                .map_err(|e| WeatherError::Other(format!("Couldn't write to {}: {}", path.display(), e)))?;
+        }
+        Some(Command::Config { .. } | Command::Quota | Command::Completions { .. } | Command::Prompt { .. }) => {
+            unreachable!("handled before fetching weather")
+        }
+    }
+
+    Ok(())
+}
+
+/// Where to get the current weather for
+#[derive(Clone, Copy)]
+enum Location<'a> {
+    Coords(f64, f64),
+    Zip(&'a str),
+}
+
+/// Whether the command uses the last --zip or --city lookup when no
+/// location is given, and remembers new lookups. Scheduled and long-running
+/// commands keep to the configured location.
+fn follows_last_lookup(args: &Args) -> bool {
+    match &args.command {
+        None => args.current.export.is_none(),
+        Some(Command::Current(current)) => current.export.is_none(),
+        Some(
+            Command::Forecast(_)
+            | Command::Alerts { .. }
+            | Command::Snow { .. }
+            | Command::Radar { .. }
+            | Command::Pollen { .. }
+            | Command::Fire { .. }
+            | Command::Storms { .. }
+            | Command::CompareProviders,
+        ) => true,
+        _ => false,
+    }
+}
+
+/// Makes a looked-up place the one used when no location is given
+fn remember_lookup(latitude: f64, longitude: f64, name: String) {
+    if let Err(e) = recent::save(&SavedLocation { latitude, longitude, name: Some(name) }) {
+        tracing::warn!(error = %e, "couldn't remember the location");
+    }
+}
+
+/// Picks the coordinates from the location options, falling back to the
+/// last lookup for commands that follow it, then the configured ones.
+/// ZIP codes, cities and saved names are looked up here.
+fn resolve_location(args: &Args, config: &Config) -> Result<(f64, f64), WeatherError> {
+    if let Some(name) = &args.location {
+        let place = config.locations.get(name).ok_or_else(|| {
+            let names: Vec<&str> = config.locations.keys().map(String::as_str).collect();
+            WeatherError::NotFound(match names.is_empty() {
+                true => format!("No saved location \"{}\", add one with `locations add`", name),
+                false => format!("No saved location \"{}\", saved ones are: {}", name, names.join(", ")),
+            })
+        })?;
+        return Ok((place.latitude, place.longitude));
+    }
+    if let Some(coords) = args.coords {
+        return Ok(coords);
+    }
+    if let (Some(lat), Some(lon)) = (args.lat, args.lon) {
+        return Ok((lat, lon));
+    }
+    if let Some(zip) = &args.zip {
+        let place = location::get_zip_place(zip, config.country.as_deref(), &config.api_key).map_err(|e| zip_error(zip, e.into()))?;
+        if follows_last_lookup(args) {
+            remember_lookup(place.lat, place.lon, place.label());
+        }
+        return Ok((place.lat, place.lon));
+    }
+    if let Some(city) = &args.city {
+        let places = location::find_places(city, config.country.as_deref(), &config.api_key)?;
+        let place = places.first().ok_or_else(|| WeatherError::NotFound(format!("No place called \"{}\" found", city)))?;
+        tracing::info!(place = %place, matches = places.len(), "city");
+        if follows_last_lookup(args) {
+            remember_lookup(place.lat, place.lon, place.label());
+        }
+        return Ok((place.lat, place.lon));
+    }
+    if args.here {
+        return Ok(location::get_ip_location()?);
+    }
+    if let Some(address) = &args.gpsd {
+        return location::get_gpsd_location(address)
+            .map_err(|e| WeatherError::Other(format!("Couldn't read a position from gpsd: {}", e)));
+    }
+    if let Some(last) = recent::load().filter(|_| !args.default && follows_last_lookup(args)) {
+        tracing::info!(place = last.name, "using the last looked up location");
+        return Ok((last.latitude, last.longitude));
+    }
+    Ok((config.latitude, config.longitude))
+}
+
+/// Fetches the current weather for each location of a batch file and prints
+/// one record for each, in the file's order. Locations that fail get an
+/// error record, or a line on stderr for CSV, and fail the run at the end.
+fn run_batch(source: &Path, format: history::LogFormat, config: &Config, units: &Units) -> Result<(), WeatherError> {
+    let lines = batch::read_locations(source)
+        .map_err(|e| WeatherError::Config(format!("Couldn't read {}: {}", source.display(), e)))?;
+    let results = batch::fetch_all(&lines, |line| -> Result<(f64, f64, CurrentWeather), WeatherError> {
+        let (lat, lon) = resolve_batch_line(line, config)?;
+        let json = provider::fetch_current(config, lat, lon)?;
+        Ok((lat, lon, provider::parse_current(config, &json).in_units(units)))
+    });
+
+    if format == history::LogFormat::Csv {
+        println!("location,latitude,longitude,{}", history::CSV_HEADER);
+    }
+    let mut failed = 0;
+    for (line, result) in lines.iter().zip(results) {
+        match (result, format) {
+            (Ok((lat, lon, weather)), history::LogFormat::Jsonl) => {
+                println!("{}", serde_json::json!({ "location": line, "latitude": lat, "longitude": lon, "current": weather }))
+            }
+            (Ok((lat, lon, weather)), history::LogFormat::Csv) => {
+                println!("{},{},{},{}", history::csv_field(line), lat, lon, history::csv_row(&weather))
+            }
+            (Err(e), format) => {
+                failed += 1;
+                match format {
+                    history::LogFormat::Jsonl => println!("{}", serde_json::json!({ "location": line, "error": e.to_string() })),
+                    history::LogFormat::Csv => eprintln!("{}: {}", line, e),
+                }
+            }
+        }
+    }
+    match failed {
+        0 => Ok(()),
+        _ => Err(WeatherError::Other(format!("{} of {} locations failed", failed, lines.len()))),
+    }
+}
+
+/// Finds the coordinates of a batch line: "lat,lon", a saved location, a
+/// ZIP code (anything with a digit in it) or else a city, taking the best match
+fn resolve_batch_line(line: &str, config: &Config) -> Result<(f64, f64), WeatherError> {
+    if let Ok(coords) = location::parse_coords(line) {
+        return Ok(coords);
+    }
+    if let Some(place) = config.locations.get(line) {
+        return Ok((place.latitude, place.longitude));
+    }
+    if line.chars().any(|c| c.is_ascii_digit()) {
+        return location::get_lat_long(line, config.country.as_deref(), &config.api_key)
+            .map_err(|e| zip_error(line, e.into()));
+    }
+    let places = location::find_places(line, config.country.as_deref(), &config.api_key)?;
+    places
+        .first()
+        .map(|place| (place.lat, place.lon))
+        .ok_or_else(|| WeatherError::NotFound(format!("No place called \"{}\" found", line)))
+}
+
+/// The daily forecast and active alerts as an RSS feed. Alerts need One
+/// Call access, so without it the feed has the forecast alone.
+fn forecast_feed(config: &Config, lat: f64, lon: f64, units: &Units) -> Result<String, WeatherError> {
+    let (api_key, lang) = (&config.api_key, &config.lang());
+    let (forecast, alerts) = http::join(
+        || forecast::get_forecast(api_key, &lat, &lon, lang),
+        || alerts::get_alerts(api_key, &lat, &lon, lang),
+    );
+    let alerts = alerts
+        .map(|json| Alert::from_owm(&json))
+        .map_err(|e| tracing::info!(error = %e, "no alerts for the feed"))
+        .unwrap_or_default();
+    Ok(feed::rss(&Forecast::from_owm(&forecast?), &alerts, lat, lon, units))
+}
+
+/// Key the current weather of a location is saved under
+fn current_cache_key(config: &Config, location: Location) -> Result<String, WeatherError> {
+    let key = match location {
+        Location::Zip(zip) => format!("zip {}", location::zip_query(zip, config.country.as_deref())),
+        Location::Coords(lat, lon) => cache::coords_key(lat, lon),
+    };
+    // Other providers answer in their own shape, so they're kept apart
+    Ok(match config.provider()? {
+        provider::Provider::OpenWeatherMap => key,
+        provider => format!("{} {}", provider.name(), key),
+    })
+}
+
+/// Prints the prompt segment from the saved weather, starting a refresh in
+/// the background when it's older than `max_age` so the prompt never waits
+/// on the network. With `refresh`, this is that background run.
+fn show_prompt(
+    config: &Config,
+    location: Location,
+    units: &Units,
+    color: prompt::Color,
+    max_age: Duration,
+    refresh: bool,
+) -> Result<(), WeatherError> {
+    let cache_key = current_cache_key(config, location)?;
+    if refresh {
+        let json = fetch_current(&CurrentArgs::default(), config, location).0?;
+        return cache::save_current(&cache_key, &json).map_err(|e| WeatherError::Other(format!("Couldn't save the weather: {}", e)));
+    }
+
+    let age = cache::current_age(&cache_key);
+    if age.is_none_or(|age| age > max_age) && cache::claim_refresh(&cache_key, prompt::RETRY_AFTER) {
+        // The same command line with --refresh, left to finish on its own
+        let spawned = std::env::current_exe().and_then(|exe| {
+            std::process::Command::new(exe)
+                .args(std::env::args_os().skip(1))
+                .arg("--refresh")
+                .stdin(Stdio::null())
+                .stdout(Stdio::null())
+                .stderr(Stdio::null())
+                .spawn()
+        });
+        if let Err(e) = spawned {
+            tracing::warn!(error = %e, "couldn't start a refresh");
+        }
+    }
+    // Until the first refresh has saved something there's nothing to show
+    let Some(json) = cache::load_current(&cache_key) else {
+        return Ok(());
+    };
+    let weather = config.provider()?.parse(&json);
+    // Twice the age means refreshes have been failing
+    let stale = age.is_some_and(|age| age > max_age * 2);
+    println!("{}", prompt::segment(&weather, units, color, config.plain(), stale));
+    Ok(())
+}
+
+/// Fetches and prints the current conditions, in whichever form the options ask for
+fn show_current(options: &CurrentArgs, config: &Config, location: Location, units: &Units) -> Result<(), WeatherError> {
+    let cache_key = current_cache_key(config, location)?;
+    let provider = config.provider()?;
+
+    let (weather, nowcast, offline) = match options.offline {
+        true => {
+            let cached = cache::load_current(&cache_key).ok_or_else(|| {
+                WeatherError::Other("No weather saved for this location yet, run once while online".to_string())
+            })?;
+            (cached, None, true)
+        }
+        false => match fetch_current(options, config, location) {
+            (Ok(json), nowcast) => {
+                if let Err(e) = cache::save_current(&cache_key, &json) {
+                    tracing::warn!(error = %e, "couldn't save the weather for offline use");
+                }
+                // The answer to a ZIP query names the place and where it is
+                if let (Location::Zip(_), Some((lat, lon))) = (location, coords_of(&json)) {
+                    let name = [&json["name"], &json["sys"]["country"]].iter().filter_map(|v| v.as_str()).collect::<Vec<_>>().join(", ");
+                    remember_lookup(lat, lon, name);
+                }
+                (json, nowcast, false)
+            }
+            // With the network or the service down, the last saved weather beats an error
+            (Err(e @ (WeatherError::Network(_) | WeatherError::Server(_))), _) => match cache::load_current(&cache_key) {
+                Some(cached) => {
+                    tracing::info!(error = %e, "showing saved weather");
+                    (cached, None, true)
+                }
+                None => return Err(e),
             },
-            Err(_) => todo!()
+            (Err(e), _) => return Err(e),
+        },
+    };
+    let current = provider.parse(&weather);
+    let banner = offline.then(|| {
+        let age = time::now() - current.updated;
+        format!("Data from {} ago (offline)", time::format_age(age))
+    });
+    // Machine-readable output stays clean, the banner goes to stderr there
+    if let Some(banner) = banner.as_ref().filter(|_| options.raw || options.query.is_some() || options.get.is_some()) {
+        eprintln!("{}", banner);
+    }
+
+    if options.raw {
+        return print_raw(&weather);
+    }
+    let observation = config.station.as_ref().filter(|_| !offline).and_then(|station| match station.observe() {
+        Ok(observation) => Some((station, observation)),
+        Err(e) => {
+            eprintln!("Couldn't read {}: {}", station.label(), e);
+            None
+        }
+    });
+    let reported = current.clone();
+    let current = match &observation {
+        Some((station, observation)) if station.blend() => station::blend(&current, observation),
+        _ => current,
+    };
+    let model = serde_json::to_value(current.in_units(units))?;
+    if let Some(filter) = &options.query {
+        return print_query(&model, filter);
+    }
+    if let Some(path) = &options.get {
+        let value = query::get(&model, path).ok_or_else(|| {
+            let fields = query::paths(&model).join(", ");
+            WeatherError::Other(format!("No field \"{}\", available fields are: {}", path, fields))
+        })?;
+        println!("{}", query::format_value(value));
+        return Ok(());
+    }
+
+    if let Some(banner) = &banner {
+        current::print_banner(banner, config.plain());
+    }
+    match (options.big, &config.render_script) {
+        (true, _) => big::print_big(&current, units),
+        // --plain asks for output a script can't promise
+        (false, Some(script)) if !config.plain() => {
+            let output = script::render(script, &model, &unit_symbols(units))
+                .map_err(|e| WeatherError::Config(format!("render_script {}: {}", script.display(), e)))?;
+            println!("{}", output.trim_end_matches('\n'));
+        }
+        _ => {
+            let card = current::CardOptions { width: layout::terminal_width(), ..config.card_options() };
+            // The wide layout has room for the forecast, which is left out if it can't be had
+            let coords = match location {
+                Location::Coords(lat, lon) => Some((lat, lon)),
+                Location::Zip(_) => coords_of(&weather),
+            };
+            let forecast = coords
+                .filter(|_| !offline && layout::for_width(card.width) == layout::Layout::Wide)
+                .and_then(|(lat, lon)| {
+                    forecast::get_forecast(&config.api_key, &lat, &lon, &config.lang())
+                        .map_err(|e| tracing::info!(error = %e, "no forecast for the wide layout"))
+                        .ok()
+                })
+                .map(|json| Forecast::from_owm(&json));
+            // The pollen row is left out where there's no pollen data
+            let pollen = coords
+                .filter(|_| !offline && card.fields.contains(&current::Field::Pollen))
+                .and_then(|(lat, lon)| {
+                    pollen::get_pollen(&lat, &lon)
+                        .and_then(|json| pollen::current(&json))
+                        .map_err(|e| tracing::info!(error = %e, "no pollen for the card"))
+                        .ok()
+                });
+            current::print_weather_info(&current, forecast.as_ref(), pollen.as_ref(), units, &card);
+            if let Some((station, observation)) = &observation {
+                station::print_observation(&station.label(), observation, &reported, provider.name(), units);
+            }
+        }
+    }
+    // The nowcast is an extra, so the run still succeeds without it
+    match nowcast {
+        Some(Ok(minutes)) => nowcast::print_nowcast(&minutes, units, config.plain()),
+        Some(Err(e)) => eprintln!("Error fetching nowcast data: {}", e),
+        None => {}
+    }
+    Ok(())
+}
+
+/// Fetches the current weather of a daemon place, along with the nowcast and
+/// alerts that decide how soon it's refreshed again. Alerts stop being asked
+/// for once the key turns out not to have access to them.
+fn refresh_place(
+    config: &Config,
+    place: &daemon::Place,
+    units: &Units,
+    alerts_available: &Cell<bool>,
+    influx: Option<&export::Influx>,
+) -> Result<daemon::Refresh, WeatherError> {
+    let (api_key, lang) = (&config.api_key, &config.lang());
+    let weather = provider::parse_current(config, &provider::fetch_current(config, place.lat, place.lon)?);
+    // A database that's down shouldn't stop the status file from updating
+    if let Some(influx) = influx {
+        if let Err(e) = influx.write(&[influx.line(&place.name, &weather)]) {
+            tracing::warn!(place = place.name, error = %e, "export failed");
+        }
+    }
+
+    // Both only sharpen the schedule, so the refresh goes on without them
+    let minutes = nowcast::get_nowcast(api_key, &place.lat, &place.lon)
+        .map_err(|e| tracing::warn!(place = place.name, error = %e, "nowcast failed"))
+        .ok();
+    let alerts = match alerts_available.get() {
+        true => match alerts::get_alerts(api_key, &place.lat, &place.lon, lang) {
+            Ok(json) => Alert::from_owm(&json),
+            Err(e) => {
+                if let WeatherError::Auth(_) = e {
+                    alerts_available.set(false);
+                }
+                tracing::warn!(place = place.name, error = %e, "alerts failed");
+                Vec::new()
+            }
+        },
+        false => Vec::new(),
+    };
+
+    let mut data = serde_json::Map::new();
+    data.insert("latitude".to_string(), Value::from(place.lat));
+    data.insert("longitude".to_string(), Value::from(place.lon));
+    data.insert("current".to_string(), serde_json::to_value(weather.in_units(units))?);
+    if let Some(minutes) = &minutes {
+        data.insert("nowcast".to_string(), Value::from(nowcast::summarize(minutes)));
+    }
+    data.insert("alerts".to_string(), serde_json::to_value(&alerts)?);
+    let conditions = schedule::Conditions {
+        condition: weather.condition,
+        temp: weather.temp,
+        precipitation_soon: minutes.is_some_and(|minutes| minutes.iter().any(|&rate| rate > 0.0)),
+        alerts: alerts.len(),
+    };
+    Ok(daemon::Refresh { data, conditions })
+}
+
+/// Minute-by-minute precipitation for the next hour, or why it's missing
+type Nowcast = Result<Vec<f64>, WeatherError>;
+
+/// Fetches the current weather, and the nowcast when asked for, together
+fn fetch_current(
+    options: &CurrentArgs,
+    config: &Config,
+    location: Location,
+) -> (Result<Value, WeatherError>, Option<Nowcast>) {
+    let (api_key, lang) = (&config.api_key, &config.lang());
+    match location {
+        // One call answers with both the weather and the ZIP's coordinates,
+        // which the nowcast then uses
+        Location::Zip(zip) => {
+            let zip = location::zip_query(zip, config.country.as_deref());
+            let weather = current::get_weather(api_key, &[("zip", &zip)], lang).map_err(|e| zip_error(&zip, e));
+            let coords = weather.as_ref().ok().and_then(coords_of);
+            let nowcast = coords
+                .filter(|_| options.nowcast)
+                .map(|(lat, lon)| nowcast::get_nowcast(api_key, &lat, &lon).map_err(WeatherError::from));
+            (weather, nowcast)
+        }
+        Location::Coords(lat, lon) => {
+            http::join(
+                || provider::fetch_current(config, lat, lon),
+                || options.nowcast.then(|| nowcast::get_nowcast(api_key, &lat, &lon).map_err(WeatherError::from)),
+            )
+        }
+    }
+}
+
+/// The configured destination for an export sink
+fn export_target(config: &Config, sink: export::Sink) -> Result<&export::Influx, WeatherError> {
+    match sink {
+        export::Sink::Influx => config
+            .influx
+            .as_ref()
+            .ok_or_else(|| WeatherError::Config("--export influx needs an `influx` section in the config".to_string())),
+    }
+}
+
+/// Unit symbols for render scripts, e.g. "°F" for `units.temperature`
+fn unit_symbols(units: &Units) -> Value {
+    serde_json::json!({
+        "temperature": units.temperature_symbol(),
+        "wind": units.wind_symbol(),
+        "pressure": units.pressure_symbol(),
+        "precipitation": units.precipitation_symbol(),
+        "distance": units.distance_symbol(),
+    })
+}
+
+/// Coordinates a current weather response is for
+fn coords_of(json: &Value) -> Option<(f64, f64)> {
+    Some((json["coord"]["lat"].as_f64()?, json["coord"]["lon"].as_f64()?))
+}
+
+/// Prints the results of a jq-style filter, one per line
+fn print_query(model: &Value, filter: &str) -> Result<(), WeatherError> {
+    for value in query::query(model, filter).map_err(WeatherError::Other)? {
+        match value {
+            Value::Null => println!("null"),
+            Value::Array(_) | Value::Object(_) => println!("{}", serde_json::to_string_pretty(&value)?),
+            value => println!("{}", query::format_value(&value)),
+        }
+    }
+    Ok(())
+}
+
+/// Writes a completion script to stdout. Profile and saved location names
+/// from the config are baked into the script as the values `--profile` and
+/// `--location` complete to, so they show up without the shell having to call
+/// back into the app.
+fn print_completions(shell: clap_complete::Shell, config_path: &Path) {
+    let mut command = Args::command();
+    // Only read an existing config, loading would create a default one
+    let config = Some(config_path)
+        .filter(|path| path.exists())
+        .and_then(|path| config::load_config(path).ok())
+        .unwrap_or_default();
+    for (arg, names) in [("profile", config.profiles.into_keys().collect::<Vec<_>>()), ("location", config.locations.into_keys().collect())] {
+        if !names.is_empty() {
+            command = command.mut_arg(arg, |arg| arg.value_parser(clap::builder::PossibleValuesParser::new(names)));
+        }
+    }
+    clap_complete::generate(shell, &mut command, "rusty_weather", &mut io::stdout());
+}
+
+/// Runs a `locations` subcommand, saving changes to the config file. `Add`
+/// without a query saves `resolved`, the place given with the location options.
+fn run_locations_command(
+    action: &LocationsCommand,
+    mut config: Config,
+    path: &Path,
+    resolved: Option<SavedLocation>,
+) -> Result<(), WeatherError> {
+    match action {
+        LocationsCommand::List => {
+            if config.locations.is_empty() {
+                println!("No saved locations, add one with `rusty_weather locations add NAME`.");
+            }
+            for (name, place) in &config.locations {
+                let label = place.name.as_deref().unwrap_or("");
+                println!("{:<12} {:>9.4}, {:>9.4}  {}", name, place.latitude, place.longitude, label);
+            }
+            if let Some(last) = recent::load() {
+                let label = last.name.as_deref().unwrap_or("");
+                println!();
+                println!("Last looked up, used when no location is given until --default:");
+                println!("{:<12} {:>9.4}, {:>9.4}  {}", "", last.latitude, last.longitude, label);
+            }
+            return Ok(());
+        }
+        LocationsCommand::Remove { name } => {
+            if config.locations.remove(name).is_none() {
+                return Err(WeatherError::NotFound(format!("No saved location \"{}\"", name)));
+            }
+            println!("Removed {}", name);
+        }
+        LocationsCommand::Add { name, query } => {
+            let place = match query {
+                Some(query) => {
+                    let places = location::find_places(query, config.country.as_deref(), &config.api_key)?;
+                    let place = pick_place(&places)
+                        .ok_or_else(|| WeatherError::NotFound("No matching location selected".to_string()))?;
+                    SavedLocation { latitude: place.lat, longitude: place.lon, name: Some(place.label()) }
+                }
+                None => resolved.ok_or_else(|| WeatherError::Other("Give a ZIP code or city to search for".to_string()))?,
+            };
+            println!("Saved {} at {:.4}, {:.4}", name, place.latitude, place.longitude);
+            config.locations.insert(name.clone(), place);
+        }
+    }
+    config::save_config(&config, path).map_err(WeatherError::config)
+}
+
+/// Runs a `config` subcommand against the file at `path`
+fn run_config_command(action: &ConfigCommand, path: &Path, profile: Option<&str>) -> Result<(), Box<dyn Error>> {
+    if let ConfigCommand::Convert { to } = action {
+        let converted = config::convert_config(path, *to)?;
+        println!("Config converted to {}", converted.display());
+        return Ok(());
+    }
+
+    let mut config = config::load_config(path)?;
+    if let ConfigCommand::Import { path: from } = action {
+        let imported = config::import_config(from, &config)?;
+        let backup = PathBuf::from(format!("{}.bak", path.display()));
+        let backed_up = path.exists();
+        if backed_up {
+            std::fs::copy(path, &backup)?;
+        }
+        config::save_config(&imported, path)?;
+        println!("Config imported from {} to {}", from.display(), path.display());
+        if backed_up {
+            println!("The previous config is saved as {}", backup.display());
         }
+        for problem in imported.with_keyring_fallback(None).validate() {
+            println!("Check the imported config: {}", problem);
+        }
+        return Ok(());
     }
 
-    match get_weather(api_key, &lat, &lon, units) {
-        Ok(json) => print_weather_info(&json),
-        Err(e) => eprintln!("Error fetching weather data: {}", e),
+    let effective = config.with_optional_profile(profile)?;
+    match action {
+        ConfigCommand::Show => {
+            let shown = match profile {
+                Some(_) => effective.redacted()?,
+                None => config.redacted()?,
+            };
+            print!("{}", config::to_string(&shown, config::Format::from_path(path))?);
+        }
+        ConfigCommand::Get { key, reveal } => {
+            let fields = match reveal {
+                true => serde_json::to_value(&effective)?,
+                false => effective.redacted()?,
+            };
+            match fields.get(key.as_str()) {
+                Some(Value::String(value)) => println!("{}", value),
+                Some(value) => println!("{}", value),
+                None => return Err(format!("Config field \"{}\" is not set", key).into()),
+            }
+        }
+        ConfigCommand::Set { key, value } => {
+            // Refuse values that introduce new problems, but don't block on existing ones
+            let before = effective.clone().with_keyring_fallback(profile).validate();
+            config.set_value(profile, key, value)?;
+            let after = config.with_optional_profile(profile)?.with_keyring_fallback(profile).validate();
+            let introduced: Vec<&String> = after.iter().filter(|problem| !before.contains(problem)).collect();
+            if let Some(problem) = introduced.first() {
+                return Err(problem.to_string().into());
+            }
+            config::save_config(&config, path)?;
+            println!("Set {} to {}", key, value);
+        }
+        ConfigCommand::Validate => {
+            let problems = effective.with_keyring_fallback(profile).validate();
+            if !problems.is_empty() {
+                for problem in &problems {
+                    println!("{}", problem);
+                }
+                return Err(format!("Config has {} problem(s)", problems.len()).into());
+            }
+            println!("Config is valid.");
+        }
+        ConfigCommand::Export { path: to, no_api_key } => {
+            let mut exported = config.with_keyring_fallback(None);
+            if *no_api_key {
+                exported = exported.without_credentials();
+            }
+            match to {
+                Some(to) => {
+                    config::save_config(&exported, to)?;
+                    println!("Config exported to {}", to.display());
+                }
+                None => print!("{}", config::to_string(&serde_json::to_value(&exported)?, config::Format::Yaml)?),
+            }
+        }
+        ConfigCommand::Convert { .. } | ConfigCommand::Import { .. } => unreachable!("handled above"),
     }
 
     Ok(())
@@ -87,21 +1317,85 @@ fn main() -> Result<(), Box<dyn Error>> {
 fn update_config(config: &mut Config) {
     println!("Press Enter to keep existing values.");
 
-    config.api_key = prompt_update("Enter API key", &config.api_key);
-    config.units = prompt_update("Enter units (imperial, metric, default(Kelvin))", &config.units.to_string());
-    let zip_code = prompt_update("Enter ZIP code (or press Enter to skip)", "");
-    if !zip_code.is_empty() {
-        match get_lat_long(&zip_code, &config.api_key) {
-            Ok((lat, lon)) => {
-                println!("Coordinates found: Latitude = {}, Longitude = {}", lat, lon);
-                config.latitude = lat;
-                config.longitude = lon;
+    loop {
+        config.api_key = prompt_update("Enter API key", &config.api_key);
+        if config.api_key.is_empty() {
+            break;
+        }
+        match check_api_key(&config.api_key) {
+            Ok(None) => {
+                println!("API key is valid.");
+                break;
+            }
+            Ok(Some(message)) => {
+                println!("The API key was rejected: {}", message);
+                println!("Newly created keys can take a couple of hours to activate.");
+                let retry = prompt_update("Re-enter the API key? (y/n)", "y");
+                if !retry.eq_ignore_ascii_case("y") {
+                    break;
+                }
             }
+            Err(e) => {
+                println!("Could not verify the API key: {}", e);
+                break;
+            }
+        }
+    }
+    config.units = prompt_update("Enter units (imperial, metric, default(Kelvin))", &config.units.to_string());
+    let country = prompt_update("Enter country code for postal codes, e.g. US, GB, DE", config.country.as_deref().unwrap_or(""));
+    config.country = Some(country).filter(|c| !c.is_empty());
+    let query = prompt_update("Enter ZIP code or city (or press Enter to skip)", "");
+    if !query.is_empty() {
+        match location::find_places(&query, config.country.as_deref(), &config.api_key) {
+            Ok(places) => match pick_place(&places) {
+                Some(place) => {
+                    println!("Coordinates found: Latitude = {}, Longitude = {}", place.lat, place.lon);
+                    config.latitude = place.lat;
+                    config.longitude = place.lon;
+                }
+                None => println!("No matching location selected, keeping existing coordinates."),
+            },
             Err(e) => println!("Failed to retrieve coordinates: {}", e),
         }
     }
 }
 
+/// Offers to keep the API key in the system keyring, leaving it out of the config file
+fn offer_keyring(config: &mut Config, profile: Option<&str>) {
+    if config.api_key.is_empty() {
+        return;
+    }
+
+    let current = if secret::get_api_key(profile).is_some() { "y" } else { "n" };
+    let answer = prompt_update("Store the API key in the system keyring instead of the config file? (y/n)", current);
+    if answer.eq_ignore_ascii_case("y") {
+        match secret::set_api_key(profile, &config.api_key) {
+            Ok(()) => {
+                println!("API key stored in the system keyring.");
+                config.api_key.clear();
+            }
+            Err(e) => println!("Could not store the API key in the keyring, keeping it in the config file: {}", e),
+        }
+    } else if let Err(e) = secret::delete_api_key(profile) {
+        println!("Could not remove the API key from the keyring: {}", e);
+    }
+}
+
+/// Lets the user choose between geocoding matches, skipping the prompt when
+/// there is only one
+fn pick_place(places: &[location::Place]) -> Option<&location::Place> {
+    if places.len() <= 1 {
+        return places.first();
+    }
+
+    println!("Multiple locations found:");
+    for (i, place) in places.iter().enumerate() {
+        println!("  {}) {}", i + 1, place);
+    }
+    let choice = prompt_update("Choose a location by number", "1");
+    choice.parse::<usize>().ok().and_then(|n| places.get(n.checked_sub(1)?))
+}
+
 /// Prompts the user for a new value, keeping the existing value if Enter is pressed
 fn prompt_update(prompt: &str, current: &str) -> String {
     println!("{} (current: {}):", prompt, current);
@@ -116,73 +1410,43 @@ fn prompt_update(prompt: &str, current: &str) -> String {
 }
 
 
-/// Saves the configuration to a YAML file
-fn save_config(config: &Config, path: &str) -> Result<(), Box<dyn Error>> {
-    let yaml_string = serde_yaml::to_string(config)?;
-    let mut file = OpenOptions::new()
-        .write(true)
-        .create(true)
-        .truncate(true)
-        .open(path)?;
-    file.write_all(yaml_string.as_bytes())?;
-    Ok(())
-}
-
-fn get_lat_long(zip_code: &str, api_key: &str) -> Result<(f64, f64), Box<dyn Error>> {
-    let url = format!(
-        "https://api.openweathermap.org/data/2.5/weather?zip={}&appid={}",
-        zip_code, api_key
+/// Makes a cheap test call to verify an API key, returning the API's message
+/// if the key was rejected.
+fn check_api_key(api_key: &str) -> Result<Option<String>, Box<dyn Error>> {
+    let url = http::with_query(
+        &owm::url("/geo/1.0/direct"),
+        &[("q", "London"), ("limit", "1"), ("appid", api_key)],
     );
 
-    let response = ureq::get(&url).call()?.into_string()?;
-    let json: Value = serde_json::from_str(&response)?;
-
-    let lat = json["coord"]["lat"].as_f64().ok_or("Latitude not found")?;
-    let lon = json["coord"]["lon"].as_f64().ok_or("Longitude not found")?;
-
-    Ok((lat, lon))
+    match http::get(&url) {
+        Ok(_) => Ok(None),
+        Err(HttpError::Status { status: 401, body }) => {
+            let json: Value = serde_json::from_str(&body).unwrap_or_default();
+            let message = json["message"].as_str().unwrap_or("Invalid API key");
+            Ok(Some(message.to_string()))
+        }
+        Err(e) => Err(e.into()),
+    }
 }
 
-/// Fetches weather data from OpenWeatherMap API and returns JSON.
-fn get_weather(api_key: &str, lat: &f64, lon: &f64, units: &str) -> Result<Value, Box<dyn Error>> {
-    let url = format!(
-        "https://api.openweathermap.org/data/2.5/weather?lat={}&lon={}&appid={}&units={}",
-        lat, lon, api_key, units
-    );
-
-    let response = ureq::get(&url).call()?.into_string()?;
-    let json: Value = serde_json::from_str(&response)?;
-
-    Ok(json)
+/// Explains a 404 for a ZIP code lookup, which otherwise just says "not found"
+fn zip_error(zip: &str, error: WeatherError) -> WeatherError {
+    match error {
+        WeatherError::NotFound(_) => WeatherError::NotFound(format!(
+            "No place found for ZIP code \"{}\". Outside the US, add a country code like \"10115,DE\".",
+            zip
+        )),
+        error => error,
+    }
 }
 
-/// Print formatted response
-fn print_weather_info(json: &Value) {
-    let city = json["name"].as_str().unwrap_or("Unknown");
-    let temp = json["main"]["temp"].as_f64().unwrap_or(0.0);
-    let temp_max = json["main"]["temp_max"].as_f64().unwrap_or(0.0);
-    let temp_min = json["main"]["temp_min"].as_f64().unwrap_or(0.0);
-    let wind_speed = json["wind"]["speed"].as_f64().unwrap_or(0.0);
-    let description = json["weather"][0]["main"].as_str().unwrap_or("Unknown");
-
-    // Define ASCII Art HashMap
-    let weather_art: HashMap<&str, Vec<&str>> = HashMap::from([
-        ("Clear", vec![" \\ | / ", "- ( ) -", " / | \\ ", "       "]),
-        ("Clouds", vec!["    .-.   ", " .-(   ). ", "(________)", "          "]),
-        ("Rain", vec!["' '' '", " ' '' ", "''  ' ", "      "]),
-        ("Snow", vec!["*  * *", " *  * ", "* *  *", "      "]),
-    ]);
-
-    // Get ASCII art for the weather condition, or fallback to default
-    let binding = vec!["   ", "   ", "   ", "   "];
-    let art = weather_art.get(description).unwrap_or(&binding);
-
-    let width = cmp::max(art[3].len(), city.len());
-
-    let city_centered = format!("{:^width$}", city, width = width);
-
-    println!("{} | Temperature: {}", format!("{:^width$}", art[0], width = width), temp);
-    println!("{} | Min: {}", format!("{:^width$}", art[1], width = width), temp_max);
-    println!("{} | Max: {}", format!("{:^width$}", art[2], width = width), temp_min);
-    println!("{} | Wind Speed: {}", city_centered, wind_speed);
+/// Prints an API response as it was received, for inspecting fields the
+/// report doesn't show
+fn print_raw(json: &Value) -> Result<(), WeatherError> {
+    let text = serde_json::to_string_pretty(json)?;
+    // Usually piped into jq or head, so a closed pipe isn't an error
+    match writeln!(io::stdout(), "{}", text) {
+        Err(e) if e.kind() != io::ErrorKind::BrokenPipe => Err(WeatherError::Other(e.to_string())),
+        _ => Ok(()),
+    }
 }
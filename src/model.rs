@@ -0,0 +1,384 @@
+use crate::comfort::{self, FeelsLikeSource};
+use crate::time;
+use crate::units::Units;
+use serde::Serialize;
+use serde_json::Value;
+
+/// Current conditions in metric units (°C, m/s, hPa), independent of the
+/// provider response they were read from.
+#[derive(Debug, Clone, Serialize)]
+pub struct CurrentWeather {
+    pub city: String,
+    /// Condition group, e.g. "Clouds" or "Rain"
+    pub condition: String,
+    /// Condition text in the requested language, e.g. "light rain"
+    pub description: String,
+    pub temp: f64,
+    /// Apparent temperature, computed when the provider doesn't report one
+    pub feels_like: f64,
+    pub feels_like_source: FeelsLikeSource,
+    pub temp_min: f64,
+    pub temp_max: f64,
+    /// Relative humidity in percent
+    pub humidity: f64,
+    /// Computed from temperature and humidity
+    pub dew_point: f64,
+    pub wind: Wind,
+    pub pressure: f64,
+    /// Visibility in meters, when reported
+    pub visibility: Option<f64>,
+    /// Cloud cover in percent
+    pub clouds: f64,
+    /// Rain and snow over the last hour in millimeters, when reported
+    pub precipitation: Option<f64>,
+    /// UNIX timestamps of sunrise, sunset and the observation itself
+    pub sunrise: i64,
+    pub sunset: i64,
+    pub updated: i64,
+    /// Offset of the location's timezone from UTC, in seconds
+    pub utc_offset: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Wind {
+    pub speed: f64,
+    /// Direction the wind blows from, in degrees clockwise from north
+    pub deg: f64,
+    /// Peak gust speed, when reported
+    pub gust: Option<f64>,
+}
+
+impl CurrentWeather {
+    /// Reads an OpenWeatherMap `/weather` response requested with `units=metric`
+    pub fn from_owm(json: &Value) -> CurrentWeather {
+        let temp = json["main"]["temp"].as_f64().unwrap_or(0.0);
+        let humidity = json["main"]["humidity"].as_f64().unwrap_or(0.0);
+        let wind_speed = json["wind"]["speed"].as_f64().unwrap_or(0.0);
+        let (feels_like, feels_like_source) = match json["main"]["feels_like"].as_f64() {
+            Some(reported) => (reported, FeelsLikeSource::Reported),
+            None => comfort::feels_like(temp, humidity, wind_speed),
+        };
+        CurrentWeather {
+            city: json["name"].as_str().unwrap_or("Unknown").to_string(),
+            condition: json["weather"][0]["main"].as_str().unwrap_or("Unknown").to_string(),
+            description: json["weather"][0]["description"].as_str().unwrap_or("Unknown").to_string(),
+            temp,
+            feels_like,
+            feels_like_source,
+            temp_min: json["main"]["temp_min"].as_f64().unwrap_or(0.0),
+            temp_max: json["main"]["temp_max"].as_f64().unwrap_or(0.0),
+            humidity,
+            dew_point: dew_point(temp, humidity),
+            wind: Wind {
+                speed: wind_speed,
+                deg: json["wind"]["deg"].as_f64().unwrap_or(0.0),
+                gust: json["wind"]["gust"].as_f64(),
+            },
+            pressure: json["main"]["pressure"].as_f64().unwrap_or(0.0),
+            visibility: json["visibility"].as_f64(),
+            clouds: json["clouds"]["all"].as_f64().unwrap_or(0.0),
+            // Rain and snow are only included while something falls
+            precipitation: Some(json["rain"]["1h"].as_f64().unwrap_or(0.0) + json["snow"]["1h"].as_f64().unwrap_or(0.0)),
+            sunrise: json["sys"]["sunrise"].as_i64().unwrap_or(0),
+            sunset: json["sys"]["sunset"].as_i64().unwrap_or(0),
+            updated: json["dt"].as_i64().unwrap_or(0),
+            utc_offset: json["timezone"].as_i64().unwrap_or(0),
+        }
+    }
+
+    /// Reads weather in this struct's own metric shape, as printed by a
+    /// provider plugin. Only `temp` is required; the feels-like and dew
+    /// point are computed when left out.
+    pub fn from_normalized(json: &Value) -> CurrentWeather {
+        let temp = json["temp"].as_f64().unwrap_or(0.0);
+        let humidity = json["humidity"].as_f64().unwrap_or(0.0);
+        let wind_speed = json["wind"]["speed"].as_f64().unwrap_or(0.0);
+        let (feels_like, feels_like_source) = match json["feels_like"].as_f64() {
+            Some(reported) => (reported, FeelsLikeSource::Reported),
+            None => comfort::feels_like(temp, humidity, wind_speed),
+        };
+        CurrentWeather {
+            city: json["city"].as_str().unwrap_or("Unknown").to_string(),
+            condition: json["condition"].as_str().unwrap_or("Unknown").to_string(),
+            description: json["description"].as_str().unwrap_or("Unknown").to_string(),
+            temp,
+            feels_like,
+            feels_like_source,
+            temp_min: json["temp_min"].as_f64().unwrap_or(temp),
+            temp_max: json["temp_max"].as_f64().unwrap_or(temp),
+            humidity,
+            dew_point: json["dew_point"].as_f64().unwrap_or_else(|| dew_point(temp, humidity)),
+            wind: Wind {
+                speed: wind_speed,
+                deg: json["wind"]["deg"].as_f64().unwrap_or(0.0),
+                gust: json["wind"]["gust"].as_f64(),
+            },
+            pressure: json["pressure"].as_f64().unwrap_or(0.0),
+            visibility: json["visibility"].as_f64(),
+            clouds: json["clouds"].as_f64().unwrap_or(0.0),
+            precipitation: json["precipitation"].as_f64(),
+            sunrise: json["sunrise"].as_i64().unwrap_or(0),
+            sunset: json["sunset"].as_i64().unwrap_or(0),
+            updated: json["updated"].as_i64().unwrap_or_else(time::now),
+            utc_offset: json["utc_offset"].as_i64().unwrap_or(0),
+        }
+    }
+
+    /// Reads an Open-Meteo `/forecast` response with the fields requested by
+    /// `provider::get_open_meteo_current`. Open-Meteo doesn't name places or
+    /// translate conditions, so the city is the coordinates and descriptions
+    /// are in English.
+    pub fn from_open_meteo(json: &Value) -> CurrentWeather {
+        let current = &json["current"];
+        let temp = current["temperature_2m"].as_f64().unwrap_or(0.0);
+        let humidity = current["relative_humidity_2m"].as_f64().unwrap_or(0.0);
+        let (condition, description) = wmo_condition(current["weather_code"].as_i64().unwrap_or(-1));
+        let (feels_like, feels_like_source) = match current["apparent_temperature"].as_f64() {
+            Some(reported) => (reported, FeelsLikeSource::Reported),
+            None => comfort::feels_like(temp, humidity, current["wind_speed_10m"].as_f64().unwrap_or(0.0)),
+        };
+        let daily = |field: &str| json["daily"][field][0].as_i64().unwrap_or(0);
+        CurrentWeather {
+            city: format!("{:.2}, {:.2}", json["latitude"].as_f64().unwrap_or(0.0), json["longitude"].as_f64().unwrap_or(0.0)),
+            condition: condition.to_string(),
+            description: description.to_string(),
+            temp,
+            feels_like,
+            feels_like_source,
+            temp_min: json["daily"]["temperature_2m_min"][0].as_f64().unwrap_or(temp),
+            temp_max: json["daily"]["temperature_2m_max"][0].as_f64().unwrap_or(temp),
+            humidity,
+            dew_point: dew_point(temp, humidity),
+            wind: Wind {
+                speed: current["wind_speed_10m"].as_f64().unwrap_or(0.0),
+                deg: current["wind_direction_10m"].as_f64().unwrap_or(0.0),
+                gust: current["wind_gusts_10m"].as_f64(),
+            },
+            pressure: current["pressure_msl"].as_f64().unwrap_or(0.0),
+            visibility: None,
+            clouds: current["cloud_cover"].as_f64().unwrap_or(0.0),
+            precipitation: current["precipitation"].as_f64(),
+            sunrise: daily("sunrise"),
+            sunset: daily("sunset"),
+            updated: current["time"].as_i64().unwrap_or(0),
+            utc_offset: json["utc_offset_seconds"].as_i64().unwrap_or(0),
+        }
+    }
+
+    /// Copy with temperatures, wind speed, pressure and visibility converted to display
+    /// units, for output where the numbers stand on their own
+    pub fn in_units(&self, units: &Units) -> CurrentWeather {
+        CurrentWeather {
+            temp: units.temperature(self.temp),
+            feels_like: units.temperature(self.feels_like),
+            temp_min: units.temperature(self.temp_min),
+            temp_max: units.temperature(self.temp_max),
+            dew_point: units.temperature(self.dew_point),
+            wind: Wind {
+                speed: units.wind(self.wind.speed),
+                gust: self.wind.gust.map(|gust| units.wind(gust)),
+                ..self.wind.clone()
+            },
+            pressure: units.pressure(self.pressure),
+            visibility: self.visibility.map(|meters| units.distance(meters)),
+            precipitation: self.precipitation.map(|mm| units.precipitation(mm)),
+            ..self.clone()
+        }
+    }
+}
+
+/// Condition group, named as OpenWeatherMap does, and description for a WMO
+/// weather code
+fn wmo_condition(code: i64) -> (&'static str, &'static str) {
+    match code {
+        0 => ("Clear", "clear sky"),
+        1 => ("Clouds", "mainly clear"),
+        2 => ("Clouds", "partly cloudy"),
+        3 => ("Clouds", "overcast"),
+        45 | 48 => ("Fog", "fog"),
+        51..=57 => ("Drizzle", "drizzle"),
+        61 | 80 => ("Rain", "light rain"),
+        63 | 81 => ("Rain", "moderate rain"),
+        65 | 82 => ("Rain", "heavy rain"),
+        66 | 67 => ("Rain", "freezing rain"),
+        71 | 77 | 85 => ("Snow", "light snow"),
+        73 => ("Snow", "moderate snow"),
+        75 | 86 => ("Snow", "heavy snow"),
+        95..=99 => ("Thunderstorm", "thunderstorm"),
+        _ => ("Unknown", "Unknown"),
+    }
+}
+
+/// Dew point in °C with the Magnus formula, accurate to a few tenths of a
+/// degree in normal weather
+pub fn dew_point(celsius: f64, humidity: f64) -> f64 {
+    const A: f64 = 17.62;
+    const B: f64 = 243.12;
+    let gamma = (humidity.max(1.0) / 100.0).ln() + A * celsius / (B + celsius);
+    B * gamma / (A - gamma)
+}
+
+/// A multi-day forecast in metric units, as 3-hour steps and as daily
+/// summaries in the location's local calendar
+#[derive(Debug, Clone, Serialize)]
+pub struct Forecast {
+    pub city: String,
+    /// Offset of the location's timezone from UTC, in seconds
+    pub utc_offset: i64,
+    pub hourly: Vec<ForecastStep>,
+    pub daily: Vec<DailyForecast>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ForecastStep {
+    /// UNIX timestamp of the start of the step
+    pub time: i64,
+    pub temp: f64,
+    pub condition: String,
+    pub description: String,
+    /// Probability of precipitation, 0 to 1
+    pub pop: f64,
+    /// Rain and snow over the step, in millimeters
+    pub precipitation: f64,
+    pub wind_speed: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DailyForecast {
+    /// Local date, "YYYY-MM-DD"
+    pub date: String,
+    /// Short weekday name, e.g. "Mon"
+    pub weekday: String,
+    pub temp: TemperatureRange,
+    /// Condition of the step closest to midday
+    pub condition: String,
+    pub description: String,
+    /// Highest probability of precipitation during the day
+    pub pop: f64,
+    /// Total rain and snow, in millimeters
+    pub precipitation: f64,
+}
+
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct TemperatureRange {
+    pub min: f64,
+    pub max: f64,
+}
+
+impl Forecast {
+    /// Reads an OpenWeatherMap `/forecast` response requested with `units=metric`
+    pub fn from_owm(json: &Value) -> Forecast {
+        let utc_offset = json["city"]["timezone"].as_i64().unwrap_or(0);
+        let hourly: Vec<ForecastStep> = json["list"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .map(|step| ForecastStep {
+                time: step["dt"].as_i64().unwrap_or(0),
+                temp: step["main"]["temp"].as_f64().unwrap_or(0.0),
+                condition: step["weather"][0]["main"].as_str().unwrap_or("Unknown").to_string(),
+                description: step["weather"][0]["description"].as_str().unwrap_or("Unknown").to_string(),
+                pop: step["pop"].as_f64().unwrap_or(0.0),
+                precipitation: step["rain"]["3h"].as_f64().unwrap_or(0.0) + step["snow"]["3h"].as_f64().unwrap_or(0.0),
+                wind_speed: step["wind"]["speed"].as_f64().unwrap_or(0.0),
+            })
+            .collect();
+
+        let mut daily: Vec<DailyForecast> = Vec::new();
+        let mut midday_distance = i64::MAX;
+        for step in &hourly {
+            let date = time::date(step.time, utc_offset);
+            // Steps are 3 hours apart, so the one nearest 12:00 local describes the day
+            let distance = ((step.time + utc_offset).rem_euclid(86_400) - 43_200).abs();
+            match daily.last_mut() {
+                Some(day) if day.date == date => {
+                    day.temp.min = day.temp.min.min(step.temp);
+                    day.temp.max = day.temp.max.max(step.temp);
+                    day.pop = day.pop.max(step.pop);
+                    day.precipitation += step.precipitation;
+                    if distance < midday_distance {
+                        day.condition = step.condition.clone();
+                        day.description = step.description.clone();
+                        midday_distance = distance;
+                    }
+                }
+                _ => {
+                    daily.push(DailyForecast {
+                        weekday: time::weekday(step.time, utc_offset).to_string(),
+                        date,
+                        temp: TemperatureRange { min: step.temp, max: step.temp },
+                        condition: step.condition.clone(),
+                        description: step.description.clone(),
+                        pop: step.pop,
+                        precipitation: step.precipitation,
+                    });
+                    midday_distance = distance;
+                }
+            }
+        }
+
+        Forecast {
+            city: json["city"]["name"].as_str().unwrap_or("Unknown").to_string(),
+            utc_offset,
+            hourly,
+            daily,
+        }
+    }
+
+    /// Copy with temperatures, wind speeds and precipitation in display units
+    pub fn in_units(&self, units: &Units) -> Forecast {
+        Forecast {
+            hourly: self
+                .hourly
+                .iter()
+                .map(|step| ForecastStep {
+                    temp: units.temperature(step.temp),
+                    precipitation: units.precipitation(step.precipitation),
+                    wind_speed: units.wind(step.wind_speed),
+                    ..step.clone()
+                })
+                .collect(),
+            daily: self
+                .daily
+                .iter()
+                .map(|day| DailyForecast {
+                    temp: TemperatureRange { min: units.temperature(day.temp.min), max: units.temperature(day.temp.max) },
+                    precipitation: units.precipitation(day.precipitation),
+                    ..day.clone()
+                })
+                .collect(),
+            ..self.clone()
+        }
+    }
+}
+
+/// A weather warning issued for the location
+#[derive(Debug, Clone, Serialize)]
+pub struct Alert {
+    /// Issuing agency, e.g. "NWS Philadelphia - Mount Holly"
+    pub sender: String,
+    /// Warning name, e.g. "Small Craft Advisory"
+    pub event: String,
+    /// UNIX timestamps of the start and end of the warning
+    pub start: i64,
+    pub end: i64,
+    pub description: String,
+}
+
+impl Alert {
+    /// Reads the `alerts` array of an OpenWeatherMap One Call response
+    pub fn from_owm(json: &Value) -> Vec<Alert> {
+        json["alerts"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .map(|alert| Alert {
+                sender: alert["sender_name"].as_str().unwrap_or("").to_string(),
+                event: alert["event"].as_str().unwrap_or("Weather alert").to_string(),
+                start: alert["start"].as_i64().unwrap_or(0),
+                end: alert["end"].as_i64().unwrap_or(0),
+                description: alert["description"].as_str().unwrap_or("").trim().to_string(),
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,105 @@
+use crate::units::Units;
+use crate::http;
+use crate::owm;
+use serde_json::Value;
+use std::error::Error;
+
+/// Precipitation rate (mm/h) below which a minute counts as dry
+const WET_THRESHOLD: f64 = 0.1;
+
+/// Fetches the precipitation rate (mm/h) for each of the next 60 minutes.
+/// Uses the One Call `minutely` array, falling back to Open-Meteo's 15-minute
+/// data for keys without a One Call subscription.
+pub fn get_nowcast(api_key: &str, lat: &f64, lon: &f64) -> Result<Vec<f64>, Box<dyn Error>> {
+    match get_one_call_minutely(api_key, lat, lon) {
+        Ok(minutes) if !minutes.is_empty() => Ok(minutes),
+        _ => get_open_meteo_minutely(lat, lon),
+    }
+}
+
+fn get_one_call_minutely(api_key: &str, lat: &f64, lon: &f64) -> Result<Vec<f64>, Box<dyn Error>> {
+    let url = format!(
+        "{}?lat={}&lon={}&exclude=current,hourly,daily,alerts&appid={}",
+        owm::url("/data/3.0/onecall"), lat, lon, api_key
+    );
+
+    let response = http::get(&url)?.into_string()?;
+    let json: Value = serde_json::from_str(&response)?;
+
+    let minutes = json["minutely"].as_array().ok_or("Minutely data not found")?;
+    Ok(minutes
+        .iter()
+        .take(60)
+        .map(|m| m["precipitation"].as_f64().unwrap_or(0.0))
+        .collect())
+}
+
+fn get_open_meteo_minutely(lat: &f64, lon: &f64) -> Result<Vec<f64>, Box<dyn Error>> {
+    let url = format!(
+        "https://api.open-meteo.com/v1/forecast?latitude={}&longitude={}&minutely_15=precipitation&forecast_minutely_15=4",
+        lat, lon
+    );
+
+    let response = http::get(&url)?.into_string()?;
+    let json: Value = serde_json::from_str(&response)?;
+
+    // Each value is the total over 15 minutes, so scale it to an hourly rate
+    let quarters = json["minutely_15"]["precipitation"]
+        .as_array()
+        .ok_or("Minutely data not found")?;
+    Ok(quarters
+        .iter()
+        .take(4)
+        .flat_map(|q| std::iter::repeat_n(q.as_f64().unwrap_or(0.0) * 4.0, 15))
+        .collect())
+}
+
+/// Describes when precipitation starts or stops within the hour
+pub fn summarize(minutes: &[f64]) -> String {
+    let wet = |rate: &f64| *rate >= WET_THRESHOLD;
+
+    match minutes.iter().position(wet) {
+        None => "No precipitation expected in the next hour".to_string(),
+        Some(0) => match minutes.iter().position(|rate| !wet(rate)) {
+            Some(stop) => format!("Rain stopping in {} minutes", stop),
+            None => "Rain continuing for the next hour".to_string(),
+        },
+        Some(start) => match minutes[start..].iter().position(|rate| !wet(rate)) {
+            Some(duration) => format!("Rain in {} minutes, stopping by {} minutes", start, start + duration),
+            None => format!("Rain in {} minutes", start),
+        },
+    }
+}
+
+/// Renders one bar character per minute, scaled up to heavy rain (8 mm/h)
+pub fn precipitation_bar(minutes: &[f64]) -> String {
+    const LEVELS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+    minutes
+        .iter()
+        .map(|rate| {
+            if *rate < WET_THRESHOLD {
+                ' '
+            } else {
+                LEVELS[((rate / 8.0 * 7.0).round() as usize).min(7)]
+            }
+        })
+        .collect()
+}
+
+/// Print the nowcast summary and precipitation bar, or just the summary
+/// when `plain`
+pub fn print_nowcast(minutes: &[f64], units: &Units, plain: bool) {
+    let peak = minutes.iter().cloned().fold(0.0, f64::max);
+    let summary = match peak >= WET_THRESHOLD {
+        true => format!("{} (up to {}/h)", summarize(minutes), units.format_precipitation(peak)),
+        false => summarize(minutes),
+    };
+    if plain {
+        println!("Nowcast: {}", summary);
+        return;
+    }
+    println!();
+    println!("{}", summary);
+    println!("now |{}| 60m", precipitation_bar(minutes));
+}
@@ -0,0 +1,21 @@
+use std::sync::OnceLock;
+
+/// OpenWeatherMap API base URL, used unless `api_base` is configured
+pub const DEFAULT_API_BASE: &str = "https://api.openweathermap.org";
+
+static API_BASE: OnceLock<String> = OnceLock::new();
+
+/// Sets the API base URL, e.g. a caching gateway or a local test server
+pub fn init(api_base: Option<String>) {
+    let _ = API_BASE.set(api_base.unwrap_or_else(|| DEFAULT_API_BASE.to_string()));
+}
+
+/// Returns the API base URL without a trailing slash
+pub fn api_base() -> &'static str {
+    API_BASE.get().map_or(DEFAULT_API_BASE, |base| base.trim_end_matches('/'))
+}
+
+/// Builds the URL for an API path like "/data/2.5/weather"
+pub fn url(path: &str) -> String {
+    format!("{}{}", api_base(), path)
+}
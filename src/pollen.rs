@@ -0,0 +1,222 @@
+use crate::error::WeatherError;
+use crate::http;
+use serde_json::Value;
+use std::io::{self, IsTerminal};
+
+/// A pollen type Open-Meteo forecasts
+pub struct Species {
+    pub name: &'static str,
+    /// Field in the Open-Meteo air quality response
+    field: &'static str,
+    pub group: Group,
+}
+
+const SPECIES: [Species; 6] = [
+    Species { name: "alder", field: "alder_pollen", group: Group::Tree },
+    Species { name: "birch", field: "birch_pollen", group: Group::Tree },
+    Species { name: "olive", field: "olive_pollen", group: Group::Tree },
+    Species { name: "grass", field: "grass_pollen", group: Group::Grass },
+    Species { name: "mugwort", field: "mugwort_pollen", group: Group::Weed },
+    Species { name: "ragweed", field: "ragweed_pollen", group: Group::Weed },
+];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Group {
+    Tree,
+    Grass,
+    Weed,
+}
+
+const GROUPS: [Group; 3] = [Group::Tree, Group::Grass, Group::Weed];
+
+/// How much pollen there is for allergy sufferers, from none to very high
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+pub enum Level {
+    None,
+    Low,
+    Moderate,
+    High,
+    VeryHigh,
+}
+
+impl Group {
+    pub fn name(self) -> &'static str {
+        match self {
+            Group::Tree => "tree",
+            Group::Grass => "grass",
+            Group::Weed => "weed",
+        }
+    }
+
+    /// Level of a count in grains/m³, on the National Allergy Bureau's
+    /// scale, where grass pollen bothers people at far lower counts than tree
+    pub fn level(self, count: f64) -> Level {
+        let [low, moderate, high, very_high] = match self {
+            Group::Tree => [1.0, 15.0, 90.0, 1500.0],
+            Group::Grass => [1.0, 5.0, 20.0, 200.0],
+            Group::Weed => [1.0, 10.0, 50.0, 500.0],
+        };
+        match count {
+            c if c >= very_high => Level::VeryHigh,
+            c if c >= high => Level::High,
+            c if c >= moderate => Level::Moderate,
+            c if c >= low => Level::Low,
+            _ => Level::None,
+        }
+    }
+}
+
+impl Level {
+    pub fn name(self) -> &'static str {
+        match self {
+            Level::None => "none",
+            Level::Low => "low",
+            Level::Moderate => "moderate",
+            Level::High => "high",
+            Level::VeryHigh => "very high",
+        }
+    }
+
+    /// Text in green, yellow, red or magenta as the level rises
+    fn paint(self, text: &str) -> String {
+        let code = match self {
+            Level::None => return text.to_string(),
+            Level::Low => 32,
+            Level::Moderate => 33,
+            Level::High => 31,
+            Level::VeryHigh => 35,
+        };
+        format!("\x1b[{}m{}\x1b[0m", code, text)
+    }
+}
+
+/// Pollen counts at one time, in grains/m³, for the species with data
+pub struct Counts(Vec<(&'static Species, f64)>);
+
+impl Counts {
+    /// Reads the counts at index `i` of the hourly forecast, or of the
+    /// current values without one. None where the location has no data.
+    fn read(section: &Value, i: Option<usize>) -> Option<Counts> {
+        let counts: Vec<(&Species, f64)> = SPECIES
+            .iter()
+            .filter_map(|species| {
+                let value = &section[species.field];
+                let count = match i {
+                    Some(i) => value[i].as_f64(),
+                    None => value.as_f64(),
+                }?;
+                Some((species, count))
+            })
+            .collect();
+        (!counts.is_empty()).then_some(Counts(counts))
+    }
+
+    /// Peak count of each group, with its level
+    fn groups(&self) -> Vec<(Group, f64, Level)> {
+        GROUPS
+            .iter()
+            .map(|&group| {
+                let peak =
+                    self.0.iter().filter(|(species, _)| species.group == group).map(|(_, count)| *count).fold(0.0, f64::max);
+                (group, peak, group.level(peak))
+            })
+            .collect()
+    }
+
+    /// Levels of each group, e.g. "tree high, grass low, weed none", with
+    /// severity colors when `color` is set
+    pub fn summary(&self, color: bool) -> String {
+        let levels: Vec<String> = self
+            .groups()
+            .iter()
+            .map(|(group, _, level)| format!("{} {}", group.name(), if color { level.paint(level.name()) } else { level.name().to_string() }))
+            .collect();
+        levels.join(", ")
+    }
+}
+
+/// Fetches current and hourly pollen counts from Open-Meteo's air quality
+/// API, which covers Europe and needs no API key
+pub fn get_pollen(lat: &f64, lon: &f64) -> Result<Value, WeatherError> {
+    let fields: Vec<&str> = SPECIES.iter().map(|species| species.field).collect();
+    let fields = fields.join(",");
+    let url = http::with_query(
+        "https://air-quality-api.open-meteo.com/v1/air-quality",
+        &[
+            ("latitude", &lat.to_string()),
+            ("longitude", &lon.to_string()),
+            ("current", &fields),
+            ("hourly", &fields),
+            ("forecast_days", "4"),
+            ("timezone", "auto"),
+        ],
+    );
+    Ok(serde_json::from_str(&http::get(&url)?.into_string()?)?)
+}
+
+/// The current pollen counts of a `get_pollen` response, or a NotFound
+/// error for places outside the forecast's coverage
+pub fn current(json: &Value) -> Result<Counts, WeatherError> {
+    Counts::read(&json["current"], None).ok_or_else(|| {
+        WeatherError::NotFound("No pollen data for this location, the pollen forecast only covers Europe".to_string())
+    })
+}
+
+/// Prints the current level of each group with the species behind it, then
+/// each day's peak level
+pub fn print_pollen(json: &Value, plain: bool) -> Result<(), WeatherError> {
+    let counts = current(json)?;
+    let color = io::stdout().is_terminal() && !plain;
+    // Padding goes inside the color so the columns stay aligned
+    let level = |level: Level, width: usize| {
+        let text = format!("{:width$}", level.name());
+        if color { level.paint(&text) } else { text }
+    };
+
+    for (group, peak, group_level) in counts.groups() {
+        let species: Vec<String> = counts
+            .0
+            .iter()
+            .filter(|(species, _)| species.group == group)
+            .map(|(species, count)| format!("{} {:.0}", species.name, count))
+            .collect();
+        match plain {
+            true => println!("{}: {}, {:.0} grains/m³ ({})", group.name(), group_level.name(), peak, species.join(", ")),
+            false => println!("{:6} {} {:>5.0} grains/m³  {}", group.name(), level(group_level, 9), peak, species.join(", ")),
+        }
+    }
+
+    // Hourly times are local "YYYY-MM-DDTHH:MM" strings, so a day is a prefix
+    let hourly = &json["hourly"];
+    let times: Vec<&str> = hourly["time"].as_array().into_iter().flatten().filter_map(Value::as_str).collect();
+    let today = json["current"]["time"].as_str().and_then(|now| now.get(..10)).unwrap_or("");
+    let mut days: Vec<(&str, Vec<Counts>)> = Vec::new();
+    for (i, time) in times.iter().enumerate() {
+        let (Some(date), Some(counts)) = (time.get(..10), Counts::read(hourly, Some(i))) else {
+            continue;
+        };
+        match days.last_mut() {
+            Some((day, hours)) if *day == date => hours.push(counts),
+            _ if date >= today => days.push((date, vec![counts])),
+            _ => {}
+        }
+    }
+    if days.is_empty() {
+        return Ok(());
+    }
+
+    println!();
+    println!("Pollen forecast:");
+    for (date, hours) in days {
+        let peaks: Vec<String> = GROUPS
+            .iter()
+            .map(|&group| {
+                let peak = hours.iter().flat_map(|counts| counts.groups()).filter(|(g, _, _)| *g == group).map(|(_, peak, _)| peak);
+                let peak_level = group.level(peak.fold(0.0, f64::max));
+                format!("{} {}", group.name(), level(peak_level, 0))
+            })
+            .collect();
+        println!("{} | {}", date, peaks.join(", "));
+    }
+    Ok(())
+}
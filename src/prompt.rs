@@ -0,0 +1,70 @@
+use crate::model::CurrentWeather;
+use crate::units::Units;
+use std::time::Duration;
+
+/// Time after a background refresh starts before another may, so a prompt
+/// drawn while offline doesn't fetch on every render
+pub const RETRY_AFTER: Duration = Duration::from_secs(60);
+
+/// How the segment's color is written, for where it's shown
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq)]
+pub enum Color {
+    /// No color
+    None,
+    /// Raw ANSI escapes, e.g. for Starship's custom module or fish
+    Ansi,
+    /// ANSI escapes marked non-printing for readline, for PS1
+    Bash,
+    /// ANSI escapes inside %{ %}, for PROMPT
+    Zsh,
+    /// #[fg=...] styles, for status-right
+    Tmux,
+    /// ${color ...} variables, for conky
+    Conky,
+}
+
+/// A short symbol for a condition group
+fn icon(condition: &str) -> &'static str {
+    match condition {
+        "Clear" => "☀",
+        "Clouds" => "☁",
+        "Rain" => "🌧",
+        "Drizzle" => "🌦",
+        "Thunderstorm" => "⛈",
+        "Snow" => "❄",
+        "Mist" | "Fog" | "Haze" | "Smoke" | "Dust" | "Sand" | "Ash" => "🌫",
+        _ => "🌡",
+    }
+}
+
+/// Color names for a temperature in Celsius, from cold to hot, as an ANSI
+/// code and the name tmux and conky know
+fn temperature_color(celsius: f64) -> (u8, &'static str) {
+    match celsius {
+        t if t < 0.0 => (36, "cyan"),
+        t if t < 10.0 => (34, "blue"),
+        t if t < 20.0 => (32, "green"),
+        t if t < 28.0 => (33, "yellow"),
+        _ => (31, "red"),
+    }
+}
+
+/// Formats the segment, e.g. "☀ 21°C", with the temperature rounded and
+/// colored by how warm it is. Plain segments name the conditions instead of
+/// the icon, and stale ones end with "?".
+pub fn segment(weather: &CurrentWeather, units: &Units, color: Color, plain: bool, stale: bool) -> String {
+    let temperature = format!("{:.0}{}{}", units.temperature(weather.temp), units.temperature_symbol(), if stale { "?" } else { "" });
+    let temperature = match (color, temperature_color(weather.temp)) {
+        (Color::None, _) => temperature,
+        (Color::Ansi, (code, _)) => format!("\x1b[{}m{}\x1b[0m", code, temperature),
+        // Bash turns \[ \] into these bytes before running $(...), so output has to use them itself
+        (Color::Bash, (code, _)) => format!("\x01\x1b[{}m\x02{}\x01\x1b[0m\x02", code, temperature),
+        (Color::Zsh, (code, _)) => format!("%{{\x1b[{}m%}}{}%{{\x1b[0m%}}", code, temperature),
+        (Color::Tmux, (_, name)) => format!("#[fg={}]{}#[default]", name, temperature),
+        (Color::Conky, (_, name)) => format!("${{color {}}}{}$color", name, temperature),
+    };
+    match plain {
+        true => format!("{} {}", weather.condition, temperature),
+        false => format!("{} {}", icon(&weather.condition), temperature),
+    }
+}
@@ -0,0 +1,152 @@
+use crate::config::Config;
+use crate::current;
+use crate::http;
+use crate::error::WeatherError;
+use crate::model::CurrentWeather;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::io::{Read, Write};
+use std::process::{Command, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+use tracing::info;
+
+/// Names of the built-in providers, OpenWeatherMap being the default
+pub const OPENWEATHERMAP: &str = "openweathermap";
+pub const OPEN_METEO: &str = "open-meteo";
+
+/// Time a plugin gets to answer before it's stopped
+const PLUGIN_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// An external program supplying the current weather, set up under
+/// `providers` in the config. It's given the request as JSON on stdin and
+/// answers with the weather as JSON on stdout.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Plugin {
+    pub command: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub args: Vec<String>,
+}
+
+/// A source of current weather
+#[derive(Debug, Clone, Copy)]
+pub enum Provider<'a> {
+    OpenWeatherMap,
+    /// Free and keyless, but without place names or translations
+    OpenMeteo,
+    Plugin(&'a str, &'a Plugin),
+}
+
+impl<'a> Provider<'a> {
+    /// Every provider that can be asked with this config: OpenWeatherMap
+    /// when there's a key, Open-Meteo, then the plugins
+    pub fn all(config: &'a Config) -> Vec<Provider<'a>> {
+        let mut providers = Vec::new();
+        if !config.api_key.is_empty() {
+            providers.push(Provider::OpenWeatherMap);
+        }
+        providers.push(Provider::OpenMeteo);
+        providers.extend(config.providers.iter().map(|(name, plugin)| Provider::Plugin(name, plugin)));
+        providers
+    }
+
+    pub fn name(&self) -> &'a str {
+        match self {
+            Provider::OpenWeatherMap => OPENWEATHERMAP,
+            Provider::OpenMeteo => OPEN_METEO,
+            Provider::Plugin(name, _) => name,
+        }
+    }
+
+    /// Fetches the current weather response for coordinates, in the
+    /// provider's own shape. Read it with `parse`.
+    pub fn fetch(&self, config: &Config, lat: f64, lon: f64) -> Result<Value, WeatherError> {
+        match self {
+            Provider::OpenWeatherMap => {
+                let (lat, lon) = (lat.to_string(), lon.to_string());
+                current::get_weather(&config.api_key, &[("lat", &lat), ("lon", &lon)], &config.lang())
+            }
+            Provider::OpenMeteo => get_open_meteo_current(lat, lon),
+            Provider::Plugin(name, plugin) => run_plugin(name, plugin, lat, lon, &config.lang()),
+        }
+    }
+
+    /// Reads a response from `fetch`
+    pub fn parse(&self, json: &Value) -> CurrentWeather {
+        match self {
+            Provider::OpenWeatherMap => CurrentWeather::from_owm(json),
+            Provider::OpenMeteo => CurrentWeather::from_open_meteo(json),
+            Provider::Plugin(..) => CurrentWeather::from_normalized(json),
+        }
+    }
+}
+
+/// Fetches the current weather response for coordinates from the
+/// configured provider. Read it with `parse_current`.
+pub fn fetch_current(config: &Config, lat: f64, lon: f64) -> Result<Value, WeatherError> {
+    config.provider()?.fetch(config, lat, lon)
+}
+
+/// Reads a response from `fetch_current` for the configured provider
+pub fn parse_current(config: &Config, json: &Value) -> CurrentWeather {
+    config.provider().unwrap_or(Provider::OpenWeatherMap).parse(json)
+}
+
+/// Fetches the current conditions from Open-Meteo in metric units, with
+/// today's range and sun times
+fn get_open_meteo_current(lat: f64, lon: f64) -> Result<Value, WeatherError> {
+    let url = format!(
+        "https://api.open-meteo.com/v1/forecast?latitude={}&longitude={}&current=temperature_2m,relative_humidity_2m,apparent_temperature,precipitation,weather_code,cloud_cover,pressure_msl,wind_speed_10m,wind_direction_10m,wind_gusts_10m&daily=temperature_2m_min,temperature_2m_max,sunrise,sunset&forecast_days=1&wind_speed_unit=ms&timeformat=unixtime&timezone=auto",
+        lat, lon
+    );
+    Ok(serde_json::from_str(&http::get(&url)?.into_string()?)?)
+}
+
+/// Runs a plugin with `{"latitude", "longitude", "lang"}` on stdin and
+/// returns the JSON it prints
+pub fn run_plugin(name: &str, plugin: &Plugin, lat: f64, lon: f64, lang: &str) -> Result<Value, WeatherError> {
+    let failed = |reason: String| WeatherError::Other(format!("Provider \"{}\" {}", name, reason));
+    let started = Instant::now();
+
+    let mut child = Command::new(&plugin.command)
+        .args(&plugin.args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::inherit())
+        .spawn()
+        .map_err(|e| WeatherError::Config(format!("Couldn't run provider \"{}\" ({}): {}", name, plugin.command, e)))?;
+
+    let request = json!({ "latitude": lat, "longitude": lon, "lang": lang });
+    if let Some(mut stdin) = child.stdin.take() {
+        // A plugin that doesn't read its input is fine, so a closed pipe isn't an error
+        let _ = stdin.write_all(request.to_string().as_bytes());
+    }
+    // Read on the side so a chatty plugin can't fill the pipe and stall
+    let mut stdout = child.stdout.take().ok_or_else(|| failed("has no output".to_string()))?;
+    let reader = thread::spawn(move || {
+        let mut output = Vec::new();
+        stdout.read_to_end(&mut output).map(|_| output)
+    });
+
+    let status = loop {
+        if let Some(status) = child.try_wait().map_err(|e| failed(e.to_string()))? {
+            break status;
+        }
+        if started.elapsed() > PLUGIN_TIMEOUT {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err(failed(format!("didn't answer within {}s", PLUGIN_TIMEOUT.as_secs())));
+        }
+        thread::sleep(Duration::from_millis(20));
+    };
+    let output = reader
+        .join()
+        .map_err(|_| failed("output couldn't be read".to_string()))?
+        .map_err(|e| failed(e.to_string()))?;
+    info!(provider = name, status = %status, elapsed_ms = started.elapsed().as_millis() as u64, "plugin");
+
+    if !status.success() {
+        return Err(failed(format!("failed ({})", status)));
+    }
+    serde_json::from_slice(&output).map_err(|e| WeatherError::Parse(format!("Provider \"{}\" printed invalid JSON: {}", name, e)))
+}
@@ -0,0 +1,157 @@
+use serde_json::Value;
+
+/// Looks up a dotted path like "wind.speed" or "alerts.0.event" in a JSON
+/// value. Numeric segments index into arrays.
+pub fn get<'a>(value: &'a Value, path: &str) -> Option<&'a Value> {
+    path.split('.').filter(|key| !key.is_empty()).try_fold(value, |value, key| match value {
+        Value::Array(items) => items.get(key.parse::<usize>().ok()?),
+        _ => value.get(key),
+    })
+}
+
+/// Formats a value for scripts: strings without quotes, numbers with at most
+/// two decimals, objects and arrays as compact JSON
+pub fn format_value(value: &Value) -> String {
+    match value {
+        Value::String(text) => text.clone(),
+        Value::Null => String::new(),
+        // Unit conversion leaves long fractions, and "71.0" reads badly in a status bar
+        Value::Number(number) if number.is_f64() => {
+            let rounded = format!("{:.2}", number.as_f64().unwrap_or(0.0));
+            rounded.trim_end_matches('0').trim_end_matches('.').to_string()
+        }
+        _ => value.to_string(),
+    }
+}
+
+/// The dotted paths of every leaf in a value, for suggesting valid keys
+pub fn paths(value: &Value) -> Vec<String> {
+    fn collect(value: &Value, prefix: &str, paths: &mut Vec<String>) {
+        let join = |key: &str| if prefix.is_empty() { key.to_string() } else { format!("{}.{}", prefix, key) };
+        match value {
+            Value::Object(map) => map.iter().for_each(|(key, value)| collect(value, &join(key), paths)),
+            Value::Array(items) => items.iter().enumerate().for_each(|(i, value)| collect(value, &join(&i.to_string()), paths)),
+            _ => paths.push(prefix.to_string()),
+        }
+    }
+
+    let mut paths = Vec::new();
+    collect(value, "", &mut paths);
+    paths
+}
+
+/// One step of a jq-style filter
+#[derive(Debug)]
+enum Step {
+    /// `.name` or `["name"]`
+    Key(String),
+    /// `[2]`, or `[-1]` counting from the end
+    Index(i64),
+    /// `[]`, which yields every element
+    Each,
+    /// `keys` and `length`
+    Builtin(String),
+}
+
+/// Evaluates a jq-style filter such as `.wind.speed`, `.alerts[0].event`,
+/// `.alerts[].event` or `.wind | keys`. Missing keys give null, like jq.
+pub fn query(value: &Value, filter: &str) -> Result<Vec<Value>, String> {
+    let mut values = vec![value.clone()];
+    for stage in filter.split('|') {
+        for step in parse(stage.trim())? {
+            values = values.iter().map(|value| apply(value, &step)).collect::<Result<Vec<_>, _>>()?.concat();
+        }
+    }
+    Ok(values)
+}
+
+fn apply(value: &Value, step: &Step) -> Result<Vec<Value>, String> {
+    Ok(match (step, value) {
+        (Step::Key(key), Value::Object(map)) => vec![map.get(key).cloned().unwrap_or(Value::Null)],
+        (Step::Key(_), Value::Null) | (Step::Index(_), Value::Null) => vec![Value::Null],
+        (Step::Index(index), Value::Array(items)) => {
+            let index = if *index < 0 { items.len() as i64 + index } else { *index };
+            vec![usize::try_from(index).ok().and_then(|i| items.get(i)).cloned().unwrap_or(Value::Null)]
+        }
+        (Step::Each, Value::Array(items)) => items.clone(),
+        (Step::Each, Value::Object(map)) => map.values().cloned().collect(),
+        (Step::Builtin(name), _) if name == "keys" => match value {
+            Value::Object(map) => vec![Value::from(map.keys().cloned().collect::<Vec<_>>())],
+            Value::Array(items) => vec![Value::from((0..items.len()).collect::<Vec<_>>())],
+            _ => return Err(format!("{} has no keys", kind(value))),
+        },
+        (Step::Builtin(name), _) if name == "length" => vec![Value::from(match value {
+            Value::Object(map) => map.len(),
+            Value::Array(items) => items.len(),
+            Value::String(text) => text.chars().count(),
+            Value::Null => 0,
+            _ => return Err(format!("{} has no length", kind(value))),
+        })],
+        (Step::Builtin(name), _) => return Err(format!("Unknown function \"{}\"", name)),
+        (Step::Key(key), _) => return Err(format!("Cannot get \"{}\" from {}", key, kind(value))),
+        (Step::Index(index), _) => return Err(format!("Cannot get [{}] from {}", index, kind(value))),
+        (Step::Each, _) => return Err(format!("Cannot iterate over {}", kind(value))),
+    })
+}
+
+fn kind(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "a boolean",
+        Value::Number(_) => "a number",
+        Value::String(_) => "a string",
+        Value::Array(_) => "an array",
+        Value::Object(_) => "an object",
+    }
+}
+
+/// Splits one stage of a filter into steps
+fn parse(stage: &str) -> Result<Vec<Step>, String> {
+    if stage == "keys" || stage == "length" {
+        return Ok(vec![Step::Builtin(stage.to_string())]);
+    }
+    if !stage.starts_with('.') {
+        return Err(format!("Filters start with \".\", e.g. \".temp\", not \"{}\"", stage));
+    }
+
+    let mut steps = Vec::new();
+    let mut rest = &stage[1..];
+    while !rest.is_empty() {
+        if let Some(after) = rest.strip_prefix('[') {
+            let end = after.find(']').ok_or_else(|| format!("Missing \"]\" in \"{}\"", stage))?;
+            let inner = after[..end].trim();
+            steps.push(match inner {
+                "" => Step::Each,
+                _ if inner.starts_with('"') => Step::Key(unquote(inner, stage)?),
+                _ => Step::Index(inner.parse().map_err(|_| format!("Invalid index \"{}\" in \"{}\"", inner, stage))?),
+            });
+            rest = &after[end + 1..];
+        } else if rest.starts_with('"') {
+            let end = rest[1..].find('"').ok_or_else(|| format!("Missing closing quote in \"{}\"", stage))? + 2;
+            steps.push(Step::Key(unquote(&rest[..end], stage)?));
+            rest = &rest[end..];
+        } else {
+            let rest_without_dot = rest.strip_prefix('.').unwrap_or(rest);
+            if rest_without_dot.starts_with(['[', '"']) {
+                rest = rest_without_dot;
+                continue;
+            }
+            let end = rest_without_dot.find(['.', '[']).unwrap_or(rest_without_dot.len());
+            let key = &rest_without_dot[..end];
+            if key.is_empty() || !key.chars().all(|c| c.is_alphanumeric() || c == '_') {
+                return Err(format!("Invalid filter \"{}\"", stage));
+            }
+            steps.push(Step::Key(key.to_string()));
+            rest = &rest_without_dot[end..];
+        }
+    }
+    Ok(steps)
+}
+
+fn unquote(quoted: &str, stage: &str) -> Result<String, String> {
+    quoted
+        .strip_prefix('"')
+        .and_then(|s| s.strip_suffix('"'))
+        .map(str::to_string)
+        .ok_or_else(|| format!("Invalid key {} in \"{}\"", quoted, stage))
+}
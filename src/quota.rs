@@ -0,0 +1,140 @@
+use crate::owm;
+use crate::time;
+use directories::ProjectDirs;
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Share of the monthly limit at which to start warning
+const WARN_AT: f64 = 0.9;
+
+/// Call limits for the API key, defaulting to the OpenWeatherMap free plan
+#[derive(Debug, Clone, Copy)]
+pub struct Limits {
+    pub per_minute: u32,
+    pub per_month: u64,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Limits {
+            per_minute: 60,
+            per_month: 1_000_000,
+        }
+    }
+}
+
+/// Calls made with the API key, persisted between runs
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Usage {
+    /// Month being counted, as "YYYY-MM"
+    pub month: String,
+    pub month_calls: u64,
+    /// UNIX timestamps of the calls in the last minute
+    pub recent: Vec<u64>,
+}
+
+static LIMITS: OnceLock<Limits> = OnceLock::new();
+static WARNED: AtomicBool = AtomicBool::new(false);
+static LOCK: Mutex<()> = Mutex::new(());
+
+/// Sets the limits to track calls against
+pub fn init(limits: Limits) {
+    let _ = LIMITS.set(limits);
+}
+
+pub fn limits() -> Limits {
+    LIMITS.get().copied().unwrap_or_default()
+}
+
+fn usage_path() -> Option<PathBuf> {
+    ProjectDirs::from("", "", "rusty_weather").map(|dirs| dirs.data_dir().join("quota.json"))
+}
+
+fn now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0)
+}
+
+/// Loads the stored usage, with calls outside the current minute and month dropped
+pub fn load_usage() -> Usage {
+    let mut usage: Usage = usage_path()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|contents| serde_json::from_str(&contents).ok())
+        .unwrap_or_default();
+
+    let now = now();
+    let month = time::year_month(now as i64);
+    if usage.month != month {
+        usage.month = month;
+        usage.month_calls = 0;
+    }
+    usage.recent.retain(|call| now.saturating_sub(*call) < 60);
+    usage
+}
+
+fn save_usage(usage: &Usage) -> Result<(), Box<dyn Error>> {
+    let path = usage_path().ok_or("Could not determine the data directory")?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(path, serde_json::to_string(usage)?)?;
+    Ok(())
+}
+
+/// Counts a call to `url` if it uses the API key, first waiting as long as
+/// needed to stay under the per-minute limit.
+pub fn before_call(url: &str) {
+    if !url.starts_with(owm::api_base()) {
+        return;
+    }
+    let _guard = LOCK.lock();
+    let limits = limits();
+
+    let mut usage = load_usage();
+    if limits.per_minute > 0 && usage.recent.len() >= limits.per_minute as usize {
+        // Wait until the oldest call in the window is a minute old
+        let oldest = usage.recent.iter().min().copied().unwrap_or(0);
+        let wait = (oldest + 60).saturating_sub(now());
+        eprintln!("Reached {} calls per minute, waiting {} seconds...", limits.per_minute, wait);
+        thread::sleep(Duration::from_secs(wait));
+        usage = load_usage();
+    }
+
+    usage.recent.push(now());
+    usage.month_calls += 1;
+    if usage.month_calls as f64 >= limits.per_month as f64 * WARN_AT && !WARNED.swap(true, Ordering::Relaxed) {
+        eprintln!(
+            "Warning: {} of {} API calls used this month",
+            usage.month_calls, limits.per_month
+        );
+    }
+
+    // Tracking is best-effort, a read-only data directory shouldn't stop requests
+    let _ = save_usage(&usage);
+}
+
+/// Prints the current usage against the limits
+pub fn print_quota() {
+    let limits = limits();
+    let usage = load_usage();
+    let percent = |used: f64, limit: f64| if limit > 0.0 { used / limit * 100.0 } else { 0.0 };
+
+    println!(
+        "Last minute: {} / {} calls ({:.0}%)",
+        usage.recent.len(),
+        limits.per_minute,
+        percent(usage.recent.len() as f64, limits.per_minute as f64)
+    );
+    println!(
+        "This month ({}): {} / {} calls ({:.1}%)",
+        usage.month,
+        usage.month_calls,
+        limits.per_month,
+        percent(usage.month_calls as f64, limits.per_month as f64)
+    );
+}
@@ -0,0 +1,156 @@
+use crate::graphics::{kitty_image, sixel_image, Graphics};
+use crate::http;
+use serde_json::Value;
+use std::error::Error;
+use std::io::Write;
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Tile size and zoom level requested from RainViewer
+const TILE_SIZE: u32 = 256;
+const ZOOM: u32 = 6;
+
+/// Size of the rendered map in terminal cells; each cell holds two pixels
+const COLUMNS: u32 = 64;
+const ROWS: u32 = 32;
+
+/// A radar snapshot as returned by RainViewer
+pub struct Frame {
+    pub time: u64,
+    pub png: Vec<u8>,
+}
+
+/// Decoded RGBA pixels
+pub struct Image {
+    pub width: u32,
+    pub height: u32,
+    pub rgba: Vec<u8>,
+}
+
+/// Fetches the most recent radar frames centered on the given coordinates,
+/// oldest first.
+pub fn get_radar_frames(lat: &f64, lon: &f64, frames: usize) -> Result<Vec<Frame>, Box<dyn Error>> {
+    let response = http::get("https://api.rainviewer.com/public/weather-maps.json")?.into_string()?;
+    let json: Value = serde_json::from_str(&response)?;
+
+    let host = json["host"].as_str().ok_or("Radar host not found")?;
+    let past = json["radar"]["past"].as_array().ok_or("Radar frames not found")?;
+
+    let past = &past[past.len().saturating_sub(frames.max(1))..];
+    let urls = past
+        .iter()
+        .map(|frame| {
+            let path = frame["path"].as_str().ok_or("Radar frame path not found")?;
+            Ok(format!("{}{}/{}/{}/{}/{}/2/1_1.png", host, path, TILE_SIZE, ZOOM, lat, lon))
+        })
+        .collect::<Result<Vec<_>, Box<dyn Error>>>()?;
+
+    // Tiles are independent, so an animation downloads them all at once
+    past.iter()
+        .zip(http::get_all(&urls))
+        .map(|(frame, tile)| {
+            Ok(Frame {
+                time: frame["time"].as_u64().unwrap_or(0),
+                png: tile?.body,
+            })
+        })
+        .collect()
+}
+
+/// Decodes a PNG tile into RGBA pixels
+pub fn decode_png(png: &[u8]) -> Result<Image, Box<dyn Error>> {
+    let mut decoder = png::Decoder::new(png);
+    decoder.set_transformations(png::Transformations::EXPAND | png::Transformations::ALPHA | png::Transformations::STRIP_16);
+    let mut reader = decoder.read_info()?;
+    let mut buffer = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut buffer)?;
+    buffer.truncate(info.buffer_size());
+
+    let rgba = match info.color_type {
+        png::ColorType::Rgba => buffer,
+        png::ColorType::GrayscaleAlpha => buffer
+            .chunks(2)
+            .flat_map(|ga| [ga[0], ga[0], ga[0], ga[1]])
+            .collect(),
+        other => return Err(format!("Unsupported radar image format: {:?}", other).into()),
+    };
+
+    Ok(Image {
+        width: info.width,
+        height: info.height,
+        rgba,
+    })
+}
+
+/// Renders an image as rows of colored half-block characters, with the
+/// requested location marked in the center.
+pub fn render_blocks(image: &Image) -> Vec<String> {
+    let pixel = |column: u32, row: u32| -> Option<(u8, u8, u8)> {
+        let x = column * image.width / COLUMNS;
+        let y = row * image.height / (ROWS * 2);
+        let i = ((y * image.width + x) * 4) as usize;
+        let p = image.rgba.get(i..i + 4)?;
+        (p[3] > 64).then_some((p[0], p[1], p[2]))
+    };
+
+    (0..ROWS)
+        .map(|row| {
+            let mut line = String::new();
+            for column in 0..COLUMNS {
+                if row == ROWS / 2 && column == COLUMNS / 2 {
+                    line.push_str("\x1b[1;97m+\x1b[0m");
+                    continue;
+                }
+                let cell = match (pixel(column, row * 2), pixel(column, row * 2 + 1)) {
+                    (None, None) => " ".to_string(),
+                    (Some((r, g, b)), None) => format!("\x1b[38;2;{};{};{}m▀\x1b[0m", r, g, b),
+                    (None, Some((r, g, b))) => format!("\x1b[38;2;{};{};{}m▄\x1b[0m", r, g, b),
+                    (Some((r, g, b)), Some((br, bg, bb))) => format!(
+                        "\x1b[38;2;{};{};{};48;2;{};{};{}m▀\x1b[0m",
+                        r, g, b, br, bg, bb
+                    ),
+                };
+                line.push_str(&cell);
+            }
+            line
+        })
+        .collect()
+}
+
+/// Prints the radar frames in sequence, redrawing in place to animate them
+pub fn print_radar(frames: &[Frame], graphics: Graphics) -> Result<(), Box<dyn Error>> {
+    let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+    let mut stdout = std::io::stdout();
+
+    for (i, frame) in frames.iter().enumerate() {
+        if i > 0 {
+            match graphics {
+                // Sixel images have no known height in rows, so start from a clear screen
+                Graphics::Sixel => write!(stdout, "\x1b[H\x1b[2J")?,
+                // Remove the previous image, then move back over it and its caption
+                Graphics::Kitty => write!(stdout, "\x1b_Ga=d\x1b\\\x1b[{}A", ROWS + 1)?,
+                _ => write!(stdout, "\x1b[{}A", ROWS + 1)?,
+            }
+        }
+
+        match graphics {
+            Graphics::Kitty => writeln!(stdout, "{}", kitty_image(&frame.png, COLUMNS, ROWS))?,
+            Graphics::Sixel => writeln!(stdout, "{}", sixel_image(&decode_png(&frame.png)?))?,
+            _ => {
+                for line in render_blocks(&decode_png(&frame.png)?) {
+                    writeln!(stdout, "{}", line)?;
+                }
+            }
+        }
+
+        let minutes_ago = now.saturating_sub(frame.time) / 60;
+        writeln!(stdout, "\x1b[2KRadar: {} minutes ago ({}/{})", minutes_ago, i + 1, frames.len())?;
+        stdout.flush()?;
+
+        if i + 1 < frames.len() {
+            thread::sleep(Duration::from_millis(500));
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,38 @@
+use crate::config::SavedLocation;
+use directories::ProjectDirs;
+use std::error::Error;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+/// The place of the last --zip or --city lookup, which stands in for the
+/// configured location until --default
+pub fn load() -> Option<SavedLocation> {
+    let contents = fs::read_to_string(path()?).ok()?;
+    serde_json::from_str(&contents).ok()
+}
+
+/// Remembers the place of a --zip or --city lookup
+pub fn save(place: &SavedLocation) -> Result<(), Box<dyn Error>> {
+    let path = path().ok_or("no state directory")?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(path, serde_json::to_string_pretty(place)?)?;
+    Ok(())
+}
+
+/// Forgets the last lookup, going back to the configured location
+pub fn forget() -> io::Result<()> {
+    match path().map(fs::remove_file) {
+        Some(Err(e)) if e.kind() != io::ErrorKind::NotFound => Err(e),
+        _ => Ok(()),
+    }
+}
+
+/// `last_location.json` in the user state directory, or the data directory
+/// where there's no such thing
+fn path() -> Option<PathBuf> {
+    let dirs = ProjectDirs::from("", "", "rusty_weather")?;
+    Some(dirs.state_dir().unwrap_or(dirs.data_dir()).join("last_location.json"))
+}
@@ -0,0 +1,149 @@
+use crate::http::{self, HttpError, Response, Transport};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use serde_json::{Value, json};
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tracing::{debug, info};
+
+/// One request and its answer, as saved in a recording
+#[derive(Debug, Clone)]
+struct Exchange {
+    /// URL with API keys redacted
+    url: String,
+    result: Result<Response, HttpError>,
+}
+
+impl Exchange {
+    fn to_json(&self) -> Value {
+        let (status, body) = match &self.result {
+            Ok(response) => (Some(response.status), Some(response.body.clone())),
+            Err(HttpError::Status { status, body }) => (Some(*status), Some(body.clone().into_bytes())),
+            Err(HttpError::Transport(message)) => {
+                return json!({ "url": self.url, "error": message });
+            }
+        };
+
+        let mut exchange = json!({ "url": self.url, "status": status });
+        // Keep text bodies readable in the file; images and the like go in as base64
+        match body.map(String::from_utf8) {
+            Some(Ok(text)) => exchange["body"] = Value::String(text),
+            Some(Err(bytes)) => exchange["body_base64"] = Value::String(BASE64.encode(bytes.into_bytes())),
+            None => {}
+        }
+        exchange
+    }
+
+    fn from_json(json: &Value) -> Result<Exchange, Box<dyn Error>> {
+        let url = json["url"].as_str().ok_or("Recorded exchange has no url")?.to_string();
+        if let Some(message) = json["error"].as_str() {
+            return Ok(Exchange { url, result: Err(HttpError::Transport(message.to_string())) });
+        }
+
+        let status = json["status"].as_u64().ok_or("Recorded exchange has no status")? as u16;
+        let body = match (json["body"].as_str(), json["body_base64"].as_str()) {
+            (Some(text), _) => text.as_bytes().to_vec(),
+            (None, Some(encoded)) => BASE64.decode(encoded)?,
+            (None, None) => Vec::new(),
+        };
+        let result = if (200..300).contains(&status) {
+            Ok(Response { status, body })
+        } else {
+            Err(HttpError::Status { status, body: String::from_utf8_lossy(&body).into_owned() })
+        };
+        Ok(Exchange { url, result })
+    }
+}
+
+/// Passes requests to another transport and saves every exchange to a file
+pub struct RecordingTransport {
+    inner: Box<dyn Transport>,
+    path: PathBuf,
+    exchanges: Mutex<Vec<Exchange>>,
+}
+
+impl RecordingTransport {
+    pub fn new(inner: Box<dyn Transport>, path: &Path) -> RecordingTransport {
+        RecordingTransport { inner, path: path.to_path_buf(), exchanges: Mutex::new(Vec::new()) }
+    }
+}
+
+impl Transport for RecordingTransport {
+    fn get(&self, url: &str) -> Result<Response, HttpError> {
+        let result = self.inner.get(url);
+
+        debug!(url = %http::redact(url), path = %self.path.display(), "recording");
+        let mut exchanges = self.exchanges.lock().unwrap_or_else(|e| e.into_inner());
+        exchanges.push(Exchange { url: http::redact(url), result: result.clone() });
+        // Rewritten after each request so the file is complete even if we exit early
+        let recording = Value::Array(exchanges.iter().map(Exchange::to_json).collect());
+        if let Err(e) = serde_json::to_string_pretty(&recording)
+            .map_err(|e| e.to_string())
+            .and_then(|text| fs::write(&self.path, text).map_err(|e| e.to_string()))
+        {
+            eprintln!("Warning: Failed to write recording to {}: {}", self.path.display(), e);
+        }
+
+        result
+    }
+
+    /// Sent on without recording, since recordings only stand in for what's read
+    fn post(&self, url: &str, headers: &[(&str, &str)], body: &[u8]) -> Result<Response, HttpError> {
+        self.inner.post(url, headers, body)
+    }
+}
+
+/// Answers requests from a recording made with `RecordingTransport`, without
+/// touching the network
+pub struct ReplayTransport {
+    exchanges: Vec<Exchange>,
+    used: Mutex<Vec<bool>>,
+}
+
+impl ReplayTransport {
+    pub fn load(path: &Path) -> Result<ReplayTransport, Box<dyn Error>> {
+        let text = fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read recording {}: {}", path.display(), e))?;
+        let json: Value = serde_json::from_str(&text)
+            .map_err(|e| format!("Failed to parse recording {}: {}", path.display(), e))?;
+        let exchanges = json
+            .as_array()
+            .ok_or("A recording should be a list of exchanges")?
+            .iter()
+            .map(Exchange::from_json)
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(ReplayTransport { used: Mutex::new(vec![false; exchanges.len()]), exchanges })
+    }
+}
+
+impl Transport for ReplayTransport {
+    /// Serves recorded exchanges for a URL in order, repeating the last
+    /// one once they've all been used
+    fn get(&self, url: &str) -> Result<Response, HttpError> {
+        let url = http::redact(url);
+        let mut used = self.used.lock().unwrap_or_else(|e| e.into_inner());
+        let matching: Vec<usize> = (0..self.exchanges.len()).filter(|&i| self.exchanges[i].url == url).collect();
+
+        let index = matching
+            .iter()
+            .copied()
+            .find(|&i| !used[i])
+            .or(matching.last().copied())
+            .ok_or_else(|| {
+                info!(url = %url, "replay miss");
+                HttpError::Transport(format!("No recorded response for {}", url))
+            })?;
+        info!(url = %url, exchange = index, reused = used[index], "replay hit");
+        used[index] = true;
+        self.exchanges[index].result.clone()
+    }
+
+    /// Answers as if the data was accepted; a replay never touches the network
+    fn post(&self, url: &str, _headers: &[(&str, &str)], body: &[u8]) -> Result<Response, HttpError> {
+        info!(url = %http::redact(url), bytes = body.len(), "replay, not sent");
+        Ok(Response { status: 204, body: Vec::new() })
+    }
+}
@@ -0,0 +1,87 @@
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+
+/// Shortest wait while precipitation or alerts call for closer watching
+const MIN_URGENT_INTERVAL: Duration = Duration::from_secs(60);
+/// Refreshes in a row without change before the interval stops growing
+const MAX_STABLE_STREAK: u32 = 2;
+/// Temperature change, in °C, still counted as stable
+const STABLE_TEMP_CHANGE: f64 = 1.0;
+
+/// What a place's latest refresh says about how soon to look again
+#[derive(Debug, Clone, PartialEq)]
+pub struct Conditions {
+    pub condition: String,
+    /// Temperature in °C
+    pub temp: f64,
+    /// Whether the nowcast has precipitation within the hour
+    pub precipitation_soon: bool,
+    /// Number of active weather alerts
+    pub alerts: usize,
+}
+
+/// Adapts the refresh interval to the weather and power state: a quarter
+/// of the base interval when precipitation is imminent or alerts are
+/// active, up to four times it while nothing changes, and twice as long
+/// again on battery.
+pub struct Policy {
+    base: Duration,
+    stable_streak: u32,
+    previous: Vec<Option<Conditions>>,
+}
+
+impl Policy {
+    pub fn new(base: Duration) -> Policy {
+        Policy { base, stable_streak: 0, previous: Vec::new() }
+    }
+
+    /// Picks the wait before the next refresh, with a short reason for the
+    /// logs. `conditions` has an entry per place, None where it failed.
+    pub fn next_interval(&mut self, conditions: Vec<Option<Conditions>>, on_battery: bool) -> (Duration, String) {
+        let urgent = conditions.iter().flatten().any(|c| c.precipitation_soon || c.alerts > 0);
+        let stable = !urgent
+            && conditions.len() == self.previous.len()
+            && conditions.iter().zip(&self.previous).all(|pair| match pair {
+                (Some(now), Some(before)) => {
+                    now.condition == before.condition && (now.temp - before.temp).abs() < STABLE_TEMP_CHANGE
+                }
+                _ => false,
+            });
+        self.stable_streak = if stable { (self.stable_streak + 1).min(MAX_STABLE_STREAK) } else { 0 };
+        self.previous = conditions;
+
+        let (mut interval, mut reason) = if urgent {
+            ((self.base / 4).max(MIN_URGENT_INTERVAL).min(self.base), "precipitation or alerts".to_string())
+        } else if self.stable_streak > 0 {
+            (self.base * 2u32.pow(self.stable_streak), "stable conditions".to_string())
+        } else {
+            (self.base, "default".to_string())
+        };
+        if on_battery {
+            interval *= 2;
+            reason.push_str(", on battery");
+        }
+        (interval, reason)
+    }
+}
+
+/// Whether the machine is running on battery, from /sys/class/power_supply.
+/// False when plugged in, on machines without a battery, and off Linux.
+pub fn on_battery() -> bool {
+    let Ok(supplies) = fs::read_dir("/sys/class/power_supply") else {
+        return false;
+    };
+    let read = |dir: &Path, name: &str| fs::read_to_string(dir.join(name)).unwrap_or_default().trim().to_string();
+
+    let mut discharging = false;
+    for supply in supplies.flatten() {
+        let dir = supply.path();
+        match read(&dir, "type").as_str() {
+            "Mains" | "USB" if read(&dir, "online") == "1" => return false,
+            "Battery" if read(&dir, "status") == "Discharging" => discharging = true,
+            _ => {}
+        }
+    }
+    discharging
+}
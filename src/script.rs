@@ -0,0 +1,36 @@
+use serde_json::Value;
+use std::error::Error;
+use std::path::Path;
+
+/// Operations a render script may run before it's stopped, so a script
+/// stuck in a loop can't hang the app
+#[cfg(feature = "scripting")]
+const MAX_OPERATIONS: u64 = 1_000_000;
+
+/// Runs a rhai render script and returns the text it evaluates to. The
+/// script sees `weather`, the current weather in display units with the
+/// same fields as --query, and `units`, the unit symbols. `fmt(x, n)`
+/// formats a number with `n` decimals.
+#[cfg(feature = "scripting")]
+pub fn render(path: &Path, weather: &Value, units: &Value) -> Result<String, Box<dyn Error>> {
+    use rhai::{Dynamic, Engine, Scope};
+
+    let mut engine = Engine::new();
+    engine.set_max_operations(MAX_OPERATIONS);
+    engine.register_fn("fmt", |value: f64, decimals: i64| format!("{:.*}", decimals.max(0) as usize, value));
+    engine.register_fn("fmt", |value: i64, decimals: i64| format!("{:.*}", decimals.max(0) as usize, value as f64));
+
+    let mut scope = Scope::new();
+    scope.push_dynamic("weather", rhai::serde::to_dynamic(weather)?);
+    scope.push_dynamic("units", rhai::serde::to_dynamic(units)?);
+    let output: Dynamic = engine.eval_file_with_scope(&mut scope, path.to_path_buf())?;
+    Ok(match output.into_immutable_string() {
+        Ok(text) => text.to_string(),
+        Err(type_name) => return Err(format!("the script must end with a string, not {}", type_name).into()),
+    })
+}
+
+#[cfg(not(feature = "scripting"))]
+pub fn render(_path: &Path, _weather: &Value, _units: &Value) -> Result<String, Box<dyn Error>> {
+    Err("This build doesn't include scripting support".into())
+}
@@ -0,0 +1,47 @@
+use std::error::Error;
+
+/// Service name the API key is stored under in the system keyring
+#[cfg(feature = "keyring")]
+const SERVICE: &str = "rusty_weather";
+
+/// Keyring entry name for a profile, so each profile can keep its own key
+#[cfg(feature = "keyring")]
+fn entry(profile: Option<&str>) -> Result<keyring::Entry, keyring::Error> {
+    keyring::Entry::new(SERVICE, profile.unwrap_or("default"))
+}
+
+/// Reads the API key for a profile from the system keyring, if one is stored
+#[cfg(feature = "keyring")]
+pub fn get_api_key(profile: Option<&str>) -> Option<String> {
+    entry(profile).and_then(|entry| entry.get_password()).ok()
+}
+
+/// Stores the API key for a profile in the system keyring
+#[cfg(feature = "keyring")]
+pub fn set_api_key(profile: Option<&str>, api_key: &str) -> Result<(), Box<dyn Error>> {
+    Ok(entry(profile)?.set_password(api_key)?)
+}
+
+/// Removes a profile's API key from the system keyring, if it is there
+#[cfg(feature = "keyring")]
+pub fn delete_api_key(profile: Option<&str>) -> Result<(), Box<dyn Error>> {
+    match entry(profile)?.delete_credential() {
+        Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[cfg(not(feature = "keyring"))]
+pub fn get_api_key(_profile: Option<&str>) -> Option<String> {
+    None
+}
+
+#[cfg(not(feature = "keyring"))]
+pub fn set_api_key(_profile: Option<&str>, _api_key: &str) -> Result<(), Box<dyn Error>> {
+    Err("This build doesn't include keyring support".into())
+}
+
+#[cfg(not(feature = "keyring"))]
+pub fn delete_api_key(_profile: Option<&str>) -> Result<(), Box<dyn Error>> {
+    Ok(())
+}
@@ -0,0 +1,110 @@
+use crate::error::WeatherError;
+use serde_json::{Value, json};
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+
+/// Address `serve` listens on by default, only reachable from this machine
+pub const DEFAULT_LISTEN: &str = "127.0.0.1:8080";
+
+/// What a path answers with
+#[derive(Clone)]
+pub enum Body {
+    Json(Value),
+    /// An RSS document
+    Rss(String),
+}
+
+/// Answers GET requests with the body from `route`, which maps a path like
+/// "/current" to its data, or None for unknown paths. Errors are JSON. Responses are cached
+/// for `ttl` so frequent polling doesn't use up the API quota.
+pub fn run(
+    listen: &str,
+    ttl: Duration,
+    route: impl Fn(&str) -> Option<Result<Body, WeatherError>>,
+) -> Result<(), WeatherError> {
+    let listener = TcpListener::bind(listen).map_err(|e| WeatherError::Other(format!("Couldn't listen on {}: {}", listen, e)))?;
+    println!("Serving weather JSON on http://{}/ (Ctrl-C to stop)", listen);
+
+    let mut cache: HashMap<String, (Instant, Body)> = HashMap::new();
+    for stream in listener.incoming() {
+        let mut stream = match stream {
+            Ok(stream) => stream,
+            Err(e) => {
+                warn!(error = %e, "accept failed");
+                continue;
+            }
+        };
+        let path = match read_request_path(&stream) {
+            Some(path) => path,
+            None => {
+                let _ = respond(&mut stream, 400, &Body::Json(json!({ "error": "Bad request" })));
+                continue;
+            }
+        };
+        info!(path, "request");
+
+        let cached = cache.get(&path).filter(|(fetched, _)| fetched.elapsed() < ttl);
+        let (status, body) = match cached {
+            Some((_, value)) => (200, value.clone()),
+            None => match route(&path) {
+                Some(Ok(value)) => {
+                    cache.insert(path.clone(), (Instant::now(), value.clone()));
+                    (200, value)
+                }
+                Some(Err(e)) => (502, Body::Json(json!({ "error": e.to_string() }))),
+                None => (404, Body::Json(json!({ "error": format!("Unknown path {}", path) }))),
+            },
+        };
+        if let Err(e) = respond(&mut stream, status, &body) {
+            warn!(error = %e, "response failed");
+        }
+    }
+    Ok(())
+}
+
+/// Reads the request line and headers, returning the path without its query
+fn read_request_path(stream: &TcpStream) -> Option<String> {
+    let _ = stream.set_read_timeout(Some(Duration::from_secs(5)));
+    let mut reader = BufReader::new(stream);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line).ok()?;
+    // Drain the headers, the body of a GET is ignored
+    let mut header = String::new();
+    while reader.read_line(&mut header).ok()? > 2 {
+        header.clear();
+    }
+
+    let mut parts = request_line.split_whitespace();
+    match (parts.next(), parts.next()) {
+        (Some("GET"), Some(target)) => {
+            let path = target.split('?').next().unwrap_or("/").trim_end_matches('/');
+            Some(if path.is_empty() { "/".to_string() } else { path.to_string() })
+        }
+        _ => None,
+    }
+}
+
+fn respond(stream: &mut TcpStream, status: u16, body: &Body) -> std::io::Result<()> {
+    let reason = match status {
+        200 => "OK",
+        400 => "Bad Request",
+        404 => "Not Found",
+        _ => "Bad Gateway",
+    };
+    let (content_type, body) = match body {
+        Body::Json(value) => ("application/json", serde_json::to_string_pretty(value).unwrap_or_default()),
+        Body::Rss(text) => ("application/rss+xml; charset=utf-8", text.clone()),
+    };
+    write!(
+        stream,
+        "HTTP/1.1 {} {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        reason,
+        content_type,
+        body.len(),
+        body
+    )
+}
@@ -0,0 +1,72 @@
+use crate::units::Units;
+use crate::http;
+use serde_json::Value;
+use std::error::Error;
+
+/// Fetches the snow report for a location from Open-Meteo, which exposes
+/// snowfall, snow depth and freezing level without an API key.
+pub fn get_snow_report(lat: &f64, lon: &f64) -> Result<Value, Box<dyn Error>> {
+    let url = format!(
+        "https://api.open-meteo.com/v1/forecast?latitude={}&longitude={}&current=temperature_2m&hourly=snowfall,snow_depth,freezing_level_height&daily=snowfall_sum&past_days=2&forecast_days=7&timezone=auto",
+        lat, lon
+    );
+
+    let response = http::get(&url)?.into_string()?;
+    let json: Value = serde_json::from_str(&response)?;
+
+    Ok(json)
+}
+
+/// Print formatted snow report
+pub fn print_snow_report(json: &Value, units: &Units) {
+    let hourly = &json["hourly"];
+    let times: Vec<&str> = hourly["time"]
+        .as_array()
+        .map(|times| times.iter().filter_map(|t| t.as_str()).collect())
+        .unwrap_or_default();
+    let snowfall = values(&hourly["snowfall"]);
+
+    // Hourly times are local "YYYY-MM-DDTHH:MM" strings, so they sort lexically
+    let now = json["current"]["time"].as_str().unwrap_or("");
+    let current = times.iter().rposition(|t| *t <= now).unwrap_or(0);
+
+    let fresh = |hours: usize| -> f64 {
+        let start = (current + 1).saturating_sub(hours);
+        snowfall.get(start..=current).map(|s| s.iter().sum()).unwrap_or(0.0)
+    };
+    let depth = values(&hourly["snow_depth"]).get(current).copied().unwrap_or(0.0);
+    let freezing_level = values(&hourly["freezing_level_height"]).get(current).copied().unwrap_or(0.0);
+
+    let imperial = units.is_imperial();
+    let (snow_unit, height_unit) = if imperial { ("in", "ft") } else { ("cm", "m") };
+    let snow = |cm: f64| if imperial { cm / 2.54 } else { cm };
+    let height = |m: f64| if imperial { m * 3.281 } else { m };
+
+    // Snow depth is reported in meters, snowfall in centimeters
+    println!("*  * * | Snow Depth: {:.0} {}", snow(depth * 100.0), snow_unit);
+    println!(" *  *  | Fresh Snow (24h): {:.1} {}", snow(fresh(24)), snow_unit);
+    println!("* *  * | Fresh Snow (48h): {:.1} {}", snow(fresh(48)), snow_unit);
+    println!("       | Freezing Level: {:.0} {}", height(freezing_level), height_unit);
+
+    let days: Vec<&str> = json["daily"]["time"]
+        .as_array()
+        .map(|days| days.iter().filter_map(|d| d.as_str()).collect())
+        .unwrap_or_default();
+    let daily_snowfall = values(&json["daily"]["snowfall_sum"]);
+    let today = now.get(..10).unwrap_or("");
+
+    println!();
+    println!("Snow forecast:");
+    for (day, cm) in days.iter().zip(daily_snowfall).filter(|(day, _)| **day >= today) {
+        let amount = snow(cm);
+        let bar = "*".repeat((cm.round() as usize).min(30));
+        println!("{} | {:>5.1} {} {}", day, amount, snow_unit, bar);
+    }
+}
+
+/// Reads a JSON array of numbers, treating missing entries as zero
+fn values(json: &Value) -> Vec<f64> {
+    json.as_array()
+        .map(|values| values.iter().map(|v| v.as_f64().unwrap_or(0.0)).collect())
+        .unwrap_or_default()
+}
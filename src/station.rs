@@ -0,0 +1,224 @@
+use crate::compare::{PRECIPITATION_SPREAD, TEMP_SPREAD, WIND_SPREAD};
+use crate::comfort;
+use crate::error::WeatherError;
+use crate::http;
+use crate::model::{self, CurrentWeather};
+use crate::time;
+use crate::units::Units;
+use crate::wind;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// Humidity difference, in percentage points, beyond which the station
+/// and the provider are said to disagree
+const HUMIDITY_SPREAD: f64 = 15.0;
+
+/// A personal weather station whose live readings are shown with the
+/// provider's, set up under `station` in the config
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Station {
+    #[serde(flatten)]
+    pub source: Source,
+    /// Shown in place of "Station", e.g. "Backyard"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// Uses the station's readings in the card instead of the provider's
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub blend: Option<bool>,
+}
+
+/// Where the station's readings come from
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum Source {
+    /// A station shared on Weather Underground, read with its API key
+    Wunderground { station_id: String, api_key: String },
+    /// An Ecowitt gateway's local API, e.g. "http://192.168.1.50"
+    Ecowitt { url: String },
+}
+
+/// Live readings of a station in metric units (°C, m/s, hPa, mm/h). Each
+/// is missing when the station has no sensor for it.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Observation {
+    pub temp: Option<f64>,
+    pub humidity: Option<f64>,
+    pub wind_speed: Option<f64>,
+    pub wind_deg: Option<f64>,
+    pub wind_gust: Option<f64>,
+    pub pressure: Option<f64>,
+    /// Current rain rate
+    pub precipitation: Option<f64>,
+    /// UNIX timestamp of the reading
+    pub updated: i64,
+}
+
+impl Station {
+    pub fn label(&self) -> String {
+        self.name.clone().unwrap_or_else(|| "Station".to_string())
+    }
+
+    pub fn blend(&self) -> bool {
+        self.blend.unwrap_or(false)
+    }
+
+    /// Fetches the station's latest readings
+    pub fn observe(&self) -> Result<Observation, WeatherError> {
+        match &self.source {
+            Source::Wunderground { station_id, api_key } => {
+                let url = http::with_query(
+                    "https://api.weather.com/v2/pws/observations/current",
+                    &[("stationId", station_id), ("format", "json"), ("units", "m"), ("numericPrecision", "decimal"), ("apiKey", api_key)],
+                );
+                let json: Value = serde_json::from_str(&http::get(&url)?.into_string()?)?;
+                Ok(from_wunderground(&json))
+            }
+            Source::Ecowitt { url } => {
+                let url = format!("{}/get_livedata_info", url.trim_end_matches('/'));
+                let json: Value = serde_json::from_str(&http::get(&url)?.into_string()?)?;
+                Ok(from_ecowitt(&json))
+            }
+        }
+    }
+}
+
+/// Reads a Weather Underground PWS current observation in metric units
+fn from_wunderground(json: &Value) -> Observation {
+    let observation = &json["observations"][0];
+    let metric = &observation["metric"];
+    let kph = |field: &str| metric[field].as_f64().map(|speed| speed / 3.6);
+    Observation {
+        temp: metric["temp"].as_f64(),
+        humidity: observation["humidity"].as_f64(),
+        wind_speed: kph("windSpeed"),
+        wind_deg: observation["winddir"].as_f64(),
+        wind_gust: kph("windGust"),
+        pressure: metric["pressure"].as_f64(),
+        precipitation: metric["precipRate"].as_f64(),
+        updated: observation["epoch"].as_i64().unwrap_or_else(time::now),
+    }
+}
+
+/// Reads an Ecowitt `get_livedata_info` answer. Values are strings in the
+/// units the gateway is set to display, e.g. "3.4 km/h" or "29.92 inHg",
+/// so each is converted back to metric.
+fn from_ecowitt(json: &Value) -> Observation {
+    // Sensors are listed by hex id, with the unit in the value or beside it
+    let reading = |list: &str, id: &str| -> Option<(f64, String)> {
+        let item = json[list].as_array()?.iter().find(|item| item["id"].as_str().is_some_and(|i| i.eq_ignore_ascii_case(id)))?;
+        let (value, unit) = split_value(item["val"].as_str()?)?;
+        Some((value, item["unit"].as_str().unwrap_or(unit).to_lowercase()))
+    };
+    let speed = |id: &str| {
+        reading("common_list", id).map(|(value, unit)| match unit.as_str() {
+            "mph" => value * 0.44704,
+            "m/s" => value,
+            "knots" => value * 0.514444,
+            _ => value / 3.6,
+        })
+    };
+    // Relative pressure is adjusted to sea level, as providers report it
+    let pressure = json["wh25"][0]["rel"].as_str().and_then(split_value).map(|(value, unit)| match unit {
+        "inHg" => value * 33.8639,
+        "mmHg" => value * 1.33322,
+        _ => value,
+    });
+    Observation {
+        temp: reading("common_list", "0x02").map(|(value, unit)| match unit.trim_start_matches('°') {
+            "f" => (value - 32.0) * 5.0 / 9.0,
+            _ => value,
+        }),
+        humidity: reading("common_list", "0x07").map(|(value, _)| value),
+        wind_speed: speed("0x0B"),
+        wind_deg: reading("common_list", "0x0A").map(|(value, _)| value),
+        wind_gust: speed("0x0C"),
+        pressure,
+        precipitation: reading("rain", "0x0E").map(|(value, unit)| match unit.starts_with("in") {
+            true => value * 25.4,
+            false => value,
+        }),
+        updated: time::now(),
+    }
+}
+
+/// Splits an Ecowitt value like "3.4 km/h" into its number and unit
+fn split_value(text: &str) -> Option<(f64, &str)> {
+    let text = text.trim();
+    let end = text.find(|c: char| !(c.is_ascii_digit() || c == '.' || c == '-')).unwrap_or(text.len());
+    Some((text[..end].parse().ok()?, text[end..].trim()))
+}
+
+/// The provider's weather with the station's readings in place of its own
+/// wherever the station has them, and the feels-like, dew point and the
+/// day's range worked out again from the result
+pub fn blend(weather: &CurrentWeather, observation: &Observation) -> CurrentWeather {
+    let temp = observation.temp.unwrap_or(weather.temp);
+    let humidity = observation.humidity.unwrap_or(weather.humidity);
+    let wind_speed = observation.wind_speed.unwrap_or(weather.wind.speed);
+    let (feels_like, feels_like_source) = comfort::feels_like(temp, humidity, wind_speed);
+    CurrentWeather {
+        temp,
+        feels_like,
+        feels_like_source,
+        temp_min: weather.temp_min.min(temp),
+        temp_max: weather.temp_max.max(temp),
+        humidity,
+        dew_point: model::dew_point(temp, humidity),
+        wind: model::Wind {
+            speed: wind_speed,
+            deg: observation.wind_deg.unwrap_or(weather.wind.deg),
+            gust: observation.wind_gust.or(weather.wind.gust),
+        },
+        pressure: observation.pressure.unwrap_or(weather.pressure),
+        precipitation: observation.precipitation.or(weather.precipitation),
+        updated: observation.updated,
+        ..weather.clone()
+    }
+}
+
+/// Prints the station's readings under the card, then a line for each
+/// measure where it differs noticeably from the provider
+pub fn print_observation(label: &str, observation: &Observation, weather: &CurrentWeather, provider: &str, units: &Units) {
+    let mut readings = Vec::new();
+    if let Some(temp) = observation.temp {
+        readings.push(units.format_temperature(temp));
+    }
+    if let Some(humidity) = observation.humidity {
+        readings.push(format!("humidity {:.0}%", humidity));
+    }
+    if let Some(speed) = observation.wind_speed {
+        let direction = observation.wind_deg.map(|deg| format!(" {}", wind::compass(deg))).unwrap_or_default();
+        readings.push(format!("wind {}{}", units.format_wind(speed), direction));
+    }
+    if let Some(rate) = observation.precipitation.filter(|&rate| rate > 0.0) {
+        readings.push(format!("rain {}/h", units.format_precipitation(rate)));
+    }
+    println!();
+    println!("{}: {}", label, readings.join(", "));
+
+    let compared = [
+        ("Temperature", observation.temp, Some(weather.temp), TEMP_SPREAD),
+        ("Humidity", observation.humidity, Some(weather.humidity), HUMIDITY_SPREAD),
+        ("Wind", observation.wind_speed, Some(weather.wind.speed), WIND_SPREAD),
+        ("Precipitation", observation.precipitation, weather.precipitation, PRECIPITATION_SPREAD),
+    ];
+    for (name, station, api, spread) in compared {
+        let (Some(station), Some(api)) = (station, api) else {
+            continue;
+        };
+        let difference = station - api;
+        if difference.abs() <= spread {
+            continue;
+        }
+        let amount = match name {
+            "Temperature" => format!("{:.1}{}", units.temperature(station) - units.temperature(api), units.temperature_symbol())
+                .trim_start_matches('-')
+                .to_string(),
+            "Humidity" => format!("{:.0}%", difference.abs()),
+            "Wind" => units.format_wind(difference.abs()),
+            _ => units.format_precipitation(difference.abs()),
+        };
+        let direction = if difference > 0.0 { "higher" } else { "lower" };
+        println!("{} at the station is {} {} than {} reports", name, amount, direction, provider);
+    }
+}
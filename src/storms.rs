@@ -0,0 +1,721 @@
+use crate::error::WeatherError;
+use crate::http;
+use crate::location;
+use crate::time::{self, TimeFormat};
+use crate::units::Units;
+use crate::wind;
+use serde_json::Value;
+
+/// Active tropical cyclones in the Atlantic and the eastern and central
+/// Pacific, from the National Hurricane Center
+const CURRENT_STORMS_URL: &str = "https://www.nhc.noaa.gov/CurrentStorms.json";
+/// Feed of the Joint Typhoon Warning Center, linking to a text warning for
+/// each storm in the other basins
+const JTWC_FEED_URL: &str = "https://www.metoc.navy.mil/jtwc/rss/jtwc.rss";
+
+/// Basins each center warns for, to say what's missing when one can't be reached
+const NHC_BASINS: &str = "the Atlantic and the eastern and central Pacific";
+const JTWC_BASINS: &str = "the western Pacific, the Indian Ocean and the southern hemisphere";
+
+/// Storms with a position or forecast point this close, in meters, are
+/// near enough to list; about 930 miles
+const NEAR: f64 = 1_500_000.0;
+
+/// The NHC gives intensity in knots and movement in mph, the JTWC both in
+/// knots; these in m/s
+const KNOT: f64 = 0.514_444;
+const MPH: f64 = 0.447_04;
+
+/// Largest size of the track map in terminal cells
+const MAP_COLUMNS: usize = 64;
+const MAP_ROWS: usize = 20;
+
+/// A forecast position from a storm's forecast advisory
+pub struct TrackPoint {
+    pub time: i64,
+    pub lat: f64,
+    pub lon: f64,
+    /// Maximum sustained wind in m/s, where the advisory gives one
+    pub wind: Option<f64>,
+}
+
+/// An active tropical cyclone
+pub struct Storm {
+    pub name: String,
+    /// NHC classification, e.g. "HU" for hurricane or "TS" for tropical storm
+    pub classification: String,
+    pub lat: f64,
+    pub lon: f64,
+    /// Maximum sustained wind in m/s
+    pub wind: f64,
+    /// Central pressure in hPa
+    pub pressure: Option<f64>,
+    /// Direction the storm is heading, in degrees
+    pub heading: Option<f64>,
+    /// Forward speed in m/s
+    pub speed: Option<f64>,
+    /// Time of the latest advisory
+    pub updated: Option<i64>,
+    /// Forecast advisory holding the track
+    advisory_url: Option<String>,
+    pub track: Vec<TrackPoint>,
+}
+
+/// The active storms from both warning centers
+pub struct ActiveStorms {
+    pub storms: Vec<Storm>,
+    /// Basins left out because their warning center couldn't be reached
+    pub missing: Vec<&'static str>,
+}
+
+/// Fetches the NHC's list of active storms, which needs no API key
+pub fn get_current_storms() -> Result<Value, WeatherError> {
+    Ok(serde_json::from_str(&http::get(CURRENT_STORMS_URL)?.into_string()?)?)
+}
+
+impl Storm {
+    /// Reads the storms of a `get_current_storms` response, without their
+    /// tracks, which `fetch_tracks` adds
+    pub fn from_nhc(json: &Value) -> Vec<Storm> {
+        json["activeStorms"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|storm| {
+                // Some numbers come as strings, e.g. "intensity": "115"
+                let number = |field: &str| match &storm[field] {
+                    Value::String(text) => text.trim().parse::<f64>().ok(),
+                    value => value.as_f64(),
+                };
+                Some(Storm {
+                    name: storm["name"].as_str()?.to_string(),
+                    classification: storm["classification"].as_str().unwrap_or("").to_string(),
+                    lat: number("latitudeNumeric")?,
+                    lon: number("longitudeNumeric")?,
+                    wind: number("intensity").unwrap_or(0.0) * KNOT,
+                    pressure: number("pressure"),
+                    heading: number("movementDir"),
+                    speed: number("movementSpeed").map(|mph| mph * MPH),
+                    updated: storm["lastUpdate"].as_str().and_then(time::parse_utc),
+                    advisory_url: storm["forecastAdvisory"]["url"].as_str().map(str::to_string),
+                    track: Vec::new(),
+                })
+            })
+            .collect()
+    }
+
+    /// What the storm is, e.g. "Hurricane" or "Tropical Storm"
+    pub fn kind(&self) -> &str {
+        match self.classification.as_str() {
+            "HU" => "Hurricane",
+            "TY" => "Typhoon",
+            "STY" => "Super Typhoon",
+            "TC" => "Tropical Cyclone",
+            "TS" => "Tropical Storm",
+            "TD" => "Tropical Depression",
+            "STS" => "Subtropical Storm",
+            "STD" => "Subtropical Depression",
+            "PTC" => "Post-Tropical Cyclone",
+            "PC" => "Potential Tropical Cyclone",
+            "" => "Tropical Cyclone",
+            other => other,
+        }
+    }
+
+    /// The closest the storm comes to a place, now or along its forecast
+    /// track: the distance in meters and the forecast time, or None for now
+    fn closest_approach(&self, lat: f64, lon: f64) -> (f64, Option<i64>) {
+        let now = (location::distance_and_bearing((lat, lon), (self.lat, self.lon)).0, None);
+        self.track
+            .iter()
+            .map(|point| (location::distance_and_bearing((lat, lon), (point.lat, point.lon)).0, Some(point.time)))
+            .fold(now, |closest, point| if point.0 < closest.0 { point } else { closest })
+    }
+}
+
+/// Saffir-Simpson category, 1 to 5, of a sustained wind in m/s, or None
+/// below hurricane force
+pub fn category(wind: f64) -> Option<u8> {
+    let knots = (wind / KNOT).round();
+    [64.0, 83.0, 96.0, 113.0, 137.0].iter().rposition(|&floor| knots >= floor).map(|i| i as u8 + 1)
+}
+
+/// Short strength of a forecast point, e.g. "cat 2" or "TS"
+fn strength(wind: f64) -> String {
+    match category(wind) {
+        Some(category) => format!("cat {}", category),
+        None if (wind / KNOT).round() >= 34.0 => "TS".to_string(),
+        None => "TD".to_string(),
+    }
+}
+
+/// Fills in each storm's forecast track from its forecast advisory. Storms
+/// whose advisory can't be fetched keep an empty track.
+pub fn fetch_tracks(storms: &mut [Storm]) {
+    let urls: Vec<String> = storms.iter().filter_map(|storm| storm.advisory_url.clone()).collect();
+    let with_advisory = storms.iter_mut().filter(|storm| storm.advisory_url.is_some());
+    for (storm, advisory) in with_advisory.zip(http::get_all(&urls)) {
+        match advisory {
+            Ok(response) => {
+                let issued = storm.updated.unwrap_or_else(time::now);
+                storm.track = parse_track(&String::from_utf8_lossy(&response.body), issued);
+            }
+            Err(e) => tracing::warn!(storm = %storm.name, "couldn't fetch the forecast advisory: {}", e),
+        }
+    }
+}
+
+/// Reads the forecast positions of a forecast advisory, the text product
+/// with lines like
+///
+/// ```text
+/// FORECAST VALID 30/0600Z 30.5N  70.9W
+/// MAX WIND  95 KT...GUSTS 115 KT.
+/// ```
+///
+/// The NHC serves it wrapped in HTML, so tags are skipped.
+fn parse_track(text: &str, issued: i64) -> Vec<TrackPoint> {
+    let mut track: Vec<TrackPoint> = Vec::new();
+    for line in text.lines().map(strip_tags) {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        match words.as_slice() {
+            ["FORECAST" | "OUTLOOK", "VALID", valid, lat, lon, ..] => {
+                if let (Some(time), Some(lat), Some(lon)) = (valid_time(valid, issued), coordinate(lat), coordinate(lon)) {
+                    track.push(TrackPoint { time, lat, lon, wind: None });
+                }
+            }
+            ["MAX", "WIND", knots, ..] => {
+                if let Some(point) = track.last_mut().filter(|point| point.wind.is_none()) {
+                    point.wind = knots.parse::<f64>().ok().map(|knots| knots * KNOT);
+                }
+            }
+            _ => {}
+        }
+    }
+    track
+}
+
+/// Fetches the storms from both warning centers at once, the NHC's with
+/// their forecast tracks. A center that can't be reached is logged and its
+/// basins noted as missing, unless neither can.
+pub fn get_active_storms() -> Result<ActiveStorms, WeatherError> {
+    let (nhc, jtwc) = http::join(get_current_storms, get_jtwc_storms);
+    let mut active = ActiveStorms { storms: Vec::new(), missing: Vec::new() };
+    match (nhc, jtwc) {
+        (Err(e), Err(_)) => return Err(e),
+        (nhc, jtwc) => {
+            match nhc {
+                Ok(json) => {
+                    let mut storms = Storm::from_nhc(&json);
+                    fetch_tracks(&mut storms);
+                    active.storms.extend(storms);
+                }
+                Err(e) => {
+                    tracing::warn!(error = %e, "couldn't fetch the NHC storms");
+                    active.missing.push(NHC_BASINS);
+                }
+            }
+            match jtwc {
+                Ok(storms) => active.storms.extend(storms),
+                Err(e) => {
+                    tracing::warn!(error = %e, "couldn't fetch the JTWC warnings");
+                    active.missing.push(JTWC_BASINS);
+                }
+            }
+        }
+    }
+    Ok(active)
+}
+
+/// Fetches the JTWC's current warnings, which need no API key. Warnings
+/// for the NHC's basins are skipped, since the NHC has those storms. A
+/// warning that can't be fetched or read is logged and left out.
+pub fn get_jtwc_storms() -> Result<Vec<Storm>, WeatherError> {
+    let feed = http::get(JTWC_FEED_URL)?.into_string()?;
+    let urls = warning_links(&feed);
+    let mut storms = Vec::new();
+    for (url, warning) in urls.iter().zip(http::get_all(&urls)) {
+        match warning.map(|response| Storm::from_jtwc(&String::from_utf8_lossy(&response.body), time::now())) {
+            Ok(Some(storm)) => storms.push(storm),
+            Ok(None) => tracing::warn!(%url, "couldn't read the JTWC warning"),
+            Err(e) => tracing::warn!(%url, "couldn't fetch the JTWC warning: {}", e),
+        }
+    }
+    Ok(storms)
+}
+
+/// The text warnings the JTWC feed links to, like
+/// `https://www.metoc.navy.mil/jtwc/products/wp0224web.txt`, once each; the
+/// file name starts with the basin and those of the NHC's are skipped
+fn warning_links(feed: &str) -> Vec<String> {
+    let mut links: Vec<String> = Vec::new();
+    for (end, suffix) in feed.match_indices("web.txt") {
+        let Some(start) = feed[..end].rfind("http") else {
+            continue;
+        };
+        let link = &feed[start..end + suffix.len()];
+        let file = link.rsplit('/').next().unwrap_or("");
+        let nhc_basin = ["al", "ep", "cp"].iter().any(|basin| file.starts_with(basin));
+        if !nhc_basin && !link.contains(|c: char| c.is_whitespace() || "\"'<>".contains(c)) && !links.iter().any(|seen| seen == link) {
+            links.push(link.to_string());
+        }
+    }
+    links
+}
+
+impl Storm {
+    /// Reads a JTWC text warning, the plain text product with lines like
+    ///
+    /// ```text
+    /// WTPN31 PGTW 240300
+    /// 1. SUPER TYPHOON 02W (MAWAR) WARNING NR 030
+    /// 240000Z --- NEAR 14.0N 143.9E
+    ///   MOVEMENT PAST SIX HOURS - 290 DEGREES AT 07 KTS
+    /// MAX SUSTAINED WINDS - 140 KT, GUSTS 170 KT
+    /// 12 HRS, VALID AT:
+    /// 241200Z --- 14.6N 142.9E
+    /// MAX SUSTAINED WINDS - 145 KT, GUSTS 175 KT
+    /// ```
+    ///
+    /// with the track included. Times give only the day of the month, so
+    /// the issue time is taken as the latest such day up to `now`.
+    pub fn from_jtwc(text: &str, now: i64) -> Option<Storm> {
+        let mut issued = None;
+        let mut title: Option<(String, String)> = None;
+        let mut position = None;
+        let (mut wind, mut heading, mut speed) = (None, None, None);
+        let mut track: Vec<TrackPoint> = Vec::new();
+        for line in text.lines() {
+            let words: Vec<&str> = line.split_whitespace().collect();
+            match words.as_slice() {
+                [product, "PGTW", time, ..] if product.starts_with("WT") && issued.is_none() => {
+                    issued = day_time(&format!("{}Z", time), now - 9 * 86_400);
+                }
+                [number, rest @ ..] if number.ends_with('.') && title.is_none() && rest.contains(&"WARNING") => {
+                    title = jtwc_title(rest);
+                }
+                [time, "---", rest @ ..] => {
+                    let at = rest.strip_prefix(&["NEAR"]).unwrap_or(rest);
+                    let (Some(lat), Some(lon)) = (at.first().and_then(|lat| coordinate(lat)), at.get(1).and_then(|lon| coordinate(lon)))
+                    else {
+                        continue;
+                    };
+                    match position {
+                        None => position = Some((lat, lon)),
+                        Some(_) => {
+                            if let Some(time) = day_time(time, issued.unwrap_or(now)) {
+                                track.push(TrackPoint { time, lat, lon, wind: None });
+                            }
+                        }
+                    }
+                }
+                ["MOVEMENT", "PAST", "SIX", "HOURS", "-", degrees, "DEGREES", "AT", knots, ..] => {
+                    heading = degrees.parse::<f64>().ok();
+                    speed = knots.parse::<f64>().ok().map(|knots| knots * KNOT);
+                }
+                ["MAX", "SUSTAINED", "WINDS", "-", knots, ..] => {
+                    let knots = knots.trim_end_matches(',').parse::<f64>().ok().map(|knots| knots * KNOT);
+                    match track.last_mut() {
+                        Some(point) if point.wind.is_none() => point.wind = knots,
+                        Some(_) => {}
+                        None if wind.is_none() => wind = knots,
+                        None => {}
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        // The remarks paragraph is wrapped anywhere, so the pressure is read from the words
+        let words: Vec<&str> = text.split_whitespace().collect();
+        let pressure = words.windows(7).find_map(|window| match window {
+            ["MINIMUM", "CENTRAL", "PRESSURE", "AT", _, "IS", millibars] => millibars.parse::<f64>().ok(),
+            _ => None,
+        });
+        let (classification, name) = title?;
+        let (lat, lon) = position?;
+        Some(Storm { name, classification, lat, lon, wind: wind?, pressure, heading, speed, updated: issued, advisory_url: None, track })
+    }
+}
+
+/// Reads the classification and name from the words after the number of a
+/// warning's title, e.g. "TYPHOON 02W (MAWAR) WARNING NR 030", naming a
+/// storm without one by its number
+fn jtwc_title(words: &[&str]) -> Option<(String, String)> {
+    let number = words.iter().position(|word| word.len() == 3 && word[..2].chars().all(|c| c.is_ascii_digit()))?;
+    let classification = match words[..number].join(" ").as_str() {
+        "HURRICANE" => "HU".to_string(),
+        "SUPER TYPHOON" => "STY".to_string(),
+        "TYPHOON" => "TY".to_string(),
+        "TROPICAL CYCLONE" => "TC".to_string(),
+        "TROPICAL STORM" => "TS".to_string(),
+        "TROPICAL DEPRESSION" => "TD".to_string(),
+        "SUBTROPICAL STORM" => "STS".to_string(),
+        "SUBTROPICAL DEPRESSION" => "STD".to_string(),
+        other => other.to_string(),
+    };
+    let name = match words.get(number + 1).and_then(|name| name.strip_prefix('(')?.strip_suffix(')')) {
+        Some(name) => name.chars().take(1).chain(name.chars().skip(1).flat_map(char::to_lowercase)).collect(),
+        None => words[number].to_string(),
+    };
+    Some((classification, name))
+}
+
+/// Resolves a JTWC "DDHHMMZ" time like `valid_time` does an NHC "DD/HHMMZ" one
+fn day_time(text: &str, issued: i64) -> Option<i64> {
+    valid_time(&format!("{}/{}", text.get(..2)?, text.get(2..)?), issued)
+}
+
+/// Whether a place is roughly in the basins the NHC warns for: north of the
+/// equator, from the dateline east to Africa
+fn in_nhc_basins(lat: f64, lon: f64) -> bool {
+    lat >= 0.0 && lon <= 0.0
+}
+
+fn strip_tags(line: &str) -> String {
+    let mut text = String::new();
+    let mut in_tag = false;
+    for c in line.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' => in_tag = false,
+            c if !in_tag => text.push(c),
+            _ => {}
+        }
+    }
+    text
+}
+
+/// Reads a coordinate like "30.5N" or "70.9W" as signed degrees, ignoring
+/// notes run on after it, as in "55.0W...POST-TROP/EXTRATROP"
+fn coordinate(text: &str) -> Option<f64> {
+    let text = text.split("...").next()?;
+    let hemisphere = text.chars().last()?;
+    let degrees: f64 = text[..text.len() - hemisphere.len_utf8()].parse().ok()?;
+    match hemisphere {
+        'N' | 'E' => Some(degrees),
+        'S' | 'W' => Some(-degrees),
+        _ => None,
+    }
+}
+
+/// Resolves a "DD/HHMMZ" forecast time, which gives neither month nor year,
+/// to the first such time from the day the advisory was issued
+fn valid_time(text: &str, issued: i64) -> Option<i64> {
+    let (day, clock) = text.strip_suffix('Z')?.split_once('/')?;
+    let day = format!("-{:02}", day.parse::<u32>().ok()?);
+    let hour: i64 = clock.get(..2)?.parse().ok()?;
+    let minute: i64 = clock.get(2..4)?.parse().ok()?;
+    let midnight = issued - issued.rem_euclid(86_400);
+    (0..10)
+        .map(|days| midnight + days * 86_400)
+        .find(|date| time::date(*date, 0).ends_with(&day))
+        .map(|date| date + hour * 3600 + minute * 60)
+}
+
+fn format_position(lat: f64, lon: f64) -> String {
+    let lat = format!("{:.1}{}", lat.abs(), if lat < 0.0 { 'S' } else { 'N' });
+    let lon = format!("{:.1}{}", lon.abs(), if lon < 0.0 { 'W' } else { 'E' });
+    format!("{} {}", lat, lon)
+}
+
+/// Prints the storms near a location, or all active storms with `all`,
+/// closest first, each with its forecast track, then a map of the tracks
+/// and what's missing if a warning center couldn't be reached
+pub fn print_storms(active: &ActiveStorms, all: bool, lat: f64, lon: f64, units: &Units, time_format: TimeFormat, plain: bool) {
+    let distance = |meters: f64| format!("{:.0} {}", units.distance(meters), units.distance_symbol());
+    let at = |timestamp: i64| format!("{} {} UTC", time::weekday(timestamp, 0), time::format_time(timestamp, 0, time_format));
+    let storms = &active.storms;
+    let missing = || {
+        for &basins in &active.missing {
+            let here = if (basins == NHC_BASINS) == in_nhc_basins(lat, lon) { ", where you are," } else { "" };
+            println!("Storms in {}{} aren't listed, since their warnings couldn't be fetched.", basins, here);
+        }
+    };
+
+    let mut shown: Vec<(&Storm, (f64, Option<i64>))> = storms
+        .iter()
+        .map(|storm| (storm, storm.closest_approach(lat, lon)))
+        .filter(|(_, (closest, _))| all || *closest <= NEAR)
+        .collect();
+    shown.sort_by(|a, b| a.1 .0.total_cmp(&b.1 .0));
+    let elsewhere = storms.len() - shown.len();
+    if storms.is_empty() {
+        match active.missing.as_slice() {
+            [] => println!("No active tropical cyclones anywhere."),
+            [basins] if *basins == NHC_BASINS => println!("No active tropical cyclones in {}.", JTWC_BASINS),
+            _ => println!("No active tropical cyclones in {}.", NHC_BASINS),
+        }
+        missing();
+        return;
+    }
+    if shown.is_empty() {
+        println!("No active tropical cyclones within {}; {} elsewhere, see --all.", distance(NEAR), elsewhere);
+        missing();
+        return;
+    }
+
+    for (i, (storm, (closest, closest_time))) in shown.iter().enumerate() {
+        if i > 0 {
+            println!();
+        }
+        match category(storm.wind).filter(|_| storm.classification == "HU") {
+            Some(category) => println!("{}. {} {}, category {}", i + 1, storm.kind(), storm.name, category),
+            None => println!("{}. {} {}", i + 1, storm.kind(), storm.name),
+        }
+
+        let mut rows = Vec::new();
+        let mut wind = units.format_wind(storm.wind);
+        if let Some(pressure) = storm.pressure {
+            wind = format!("{}, pressure {}", wind, units.format_pressure(pressure));
+        }
+        rows.push(("Wind", wind));
+        let (away, bearing) = location::distance_and_bearing((lat, lon), (storm.lat, storm.lon));
+        rows.push(("Position", format!("{}, {} {} of you", format_position(storm.lat, storm.lon), distance(away), wind::compass(bearing))));
+        let movement = match (storm.heading, storm.speed) {
+            (_, Some(speed)) if speed < 0.5 => "stationary".to_string(),
+            (Some(heading), Some(speed)) => format!("{} at {}", wind::compass(heading), units.format_wind(speed)),
+            _ => "unknown".to_string(),
+        };
+        rows.push(("Moving", movement));
+        if let Some(time) = closest_time {
+            rows.push(("Closest", format!("{} from you, {}", distance(*closest), at(*time))));
+        }
+        if let Some(updated) = storm.updated {
+            rows.push(("Advisory", at(updated)));
+        }
+
+        let width = rows.iter().map(|(label, _)| label.len()).max().unwrap_or(0) + 1;
+        for (label, value) in &rows {
+            match plain {
+                true => println!("{}: {}", label, value),
+                false => println!("   {:width$} {}", format!("{}:", label), value),
+            }
+        }
+        if !storm.track.is_empty() && !plain {
+            println!("   Forecast track:");
+        }
+        for point in &storm.track {
+            let position = format_position(point.lat, point.lon);
+            let away = distance(location::distance_and_bearing((lat, lon), (point.lat, point.lon)).0);
+            match (plain, point.wind) {
+                (true, Some(wind)) => {
+                    println!("Forecast: {}, {}, {}, {}, {}", at(point.time), position, away, strength(wind), units.format_wind(wind))
+                }
+                (true, None) => println!("Forecast: {}, {}, {}", at(point.time), position, away),
+                (false, Some(wind)) => {
+                    println!("     {}  {:>12}  {:>8}  {:5}  {}", at(point.time), position, away, strength(wind), units.format_wind(wind))
+                }
+                (false, None) => println!("     {}  {:>12}  {:>8}", at(point.time), position, away),
+            }
+        }
+    }
+    if elsewhere > 0 {
+        println!();
+        println!("{} more active elsewhere, see --all.", elsewhere);
+    }
+    if !active.missing.is_empty() {
+        println!();
+        missing();
+    }
+
+    if !plain {
+        let storms: Vec<&Storm> = shown.iter().map(|(storm, _)| *storm).collect();
+        println!();
+        track_map(&storms, lat, lon).iter().for_each(|line| println!("{}", line));
+    }
+}
+
+/// Draws storm positions as their number in the list, forecast points as
+/// `*` joined by dots and the location as `+`, north up, without coastlines
+fn track_map(storms: &[&Storm], lat: f64, lon: f64) -> Vec<String> {
+    let mut points = vec![(lat, lon)];
+    for storm in storms {
+        points.push((storm.lat, storm.lon));
+        points.extend(storm.track.iter().map(|point| (point.lat, point.lon)));
+    }
+    // A couple of degrees around everything, so nothing sits on the border
+    let south = points.iter().map(|p| p.0).fold(f64::INFINITY, f64::min) - 2.0;
+    let north = points.iter().map(|p| p.0).fold(f64::NEG_INFINITY, f64::max) + 2.0;
+    let west = points.iter().map(|p| p.1).fold(f64::INFINITY, f64::min) - 2.0;
+    let east = points.iter().map(|p| p.1).fold(f64::NEG_INFINITY, f64::max) + 2.0;
+
+    // Degrees of latitude per column, with longitude shrunk toward the poles;
+    // a cell is about twice as tall as it is wide, so a row covers two columns' worth
+    let shrink = ((south + north) / 2.0).to_radians().cos();
+    let step = ((east - west) * shrink / (MAP_COLUMNS - 1) as f64).max((north - south) / (2.0 * (MAP_ROWS - 1) as f64));
+    let columns = ((east - west) * shrink / step).round() as usize + 1;
+    let rows = ((north - south) / (2.0 * step)).round() as usize + 1;
+    let cell = |(lat, lon): (f64, f64)| {
+        let row = ((north - lat) / (2.0 * step)).round() as isize;
+        let column = ((lon - west) * shrink / step).round() as isize;
+        (row, column)
+    };
+
+    let mut grid = vec![vec![' '; columns]; rows];
+    let mut mark = |(row, column): (isize, isize), c: char, over: bool| {
+        if let Some(spot) = grid.get_mut(row as usize).and_then(|line| line.get_mut(column as usize)) {
+            if over || *spot == ' ' {
+                *spot = c;
+            }
+        }
+    };
+    for storm in storms {
+        let path: Vec<(isize, isize)> =
+            std::iter::once((storm.lat, storm.lon)).chain(storm.track.iter().map(|point| (point.lat, point.lon))).map(cell).collect();
+        for pair in path.windows(2) {
+            let ((r0, c0), (r1, c1)) = (pair[0], pair[1]);
+            let steps = (r1 - r0).abs().max((c1 - c0).abs()).max(1);
+            for i in 1..steps {
+                mark((r0 + (r1 - r0) * i / steps, c0 + (c1 - c0) * i / steps), '.', false);
+            }
+        }
+        path.iter().skip(1).for_each(|&spot| mark(spot, '*', true));
+    }
+    mark(cell((lat, lon)), '+', true);
+    for (i, storm) in storms.iter().enumerate() {
+        let number = char::from_digit((i + 1) as u32, 10).unwrap_or('#');
+        mark(cell((storm.lat, storm.lon)), number, true);
+    }
+
+    let latitude = |lat: f64| format!("{:.0}{}", lat.abs(), if lat < 0.0 { 'S' } else { 'N' });
+    let longitude = |lon: f64| format!("{:.0}{}", lon.abs(), if lon < 0.0 { 'W' } else { 'E' });
+    let border = format!("     +{}+", "-".repeat(columns));
+    let mut lines = vec![border.clone()];
+    for (i, line) in grid.iter().enumerate() {
+        let label = match i {
+            0 => latitude(north),
+            i if i == rows - 1 => latitude(south),
+            _ => String::new(),
+        };
+        lines.push(format!("{:>4} |{}|", label, line.iter().collect::<String>()));
+    }
+    lines.push(border);
+    let (west, east) = (longitude(west), longitude(east));
+    lines.push(format!("      {}{:>width$}", west, east, width = columns.saturating_sub(west.len())));
+
+    let mut legend: Vec<String> = storms.iter().enumerate().map(|(i, storm)| format!("{} {}", i + 1, storm.name)).collect();
+    legend.push("* forecast position".to_string());
+    legend.push("+ you".to_string());
+    lines.push(String::new());
+    lines.push(format!("      {}", legend.join("   ")));
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 2026-10-14T21:00:00Z, when the advisories below were issued
+    const ISSUED: i64 = 1_792_011_600;
+
+    const ADVISORY: &str = "<html><body><pre>
+HURRICANE FRANKLIN FORECAST/ADVISORY NUMBER  13
+MAX SUSTAINED WINDS 115 KT WITH GUSTS TO 140 KT.
+
+FORECAST VALID 15/0600Z 35.0N  71.5W
+MAX WIND 105 KT...GUSTS 130 KT.
+64 KT... 40NE  40SE  30SW  30NW.
+
+OUTLOOK VALID 18/1800Z 45.0N  55.0W...POST-TROP/EXTRATROP
+MAX WIND  50 KT...GUSTS  65 KT.
+
+OUTLOOK VALID 19/1800Z...DISSIPATED
+</pre></body></html>";
+
+    const WARNING: &str = "WTPN31 PGTW 140300
+1. SUPER TYPHOON 02W (MAWAR) WARNING NR 030
+   MAX SUSTAINED WINDS BASED ON ONE-MINUTE AVERAGE
+   WARNING POSITION:
+   140000Z --- NEAR 14.0N 143.9E
+     MOVEMENT PAST SIX HOURS - 290 DEGREES AT 07 KTS
+   MAX SUSTAINED WINDS - 140 KT, GUSTS 170 KT
+   FORECASTS:
+   12 HRS, VALID AT:
+   141200Z --- 14.6N 142.9E
+   MAX SUSTAINED WINDS - 145 KT, GUSTS 175 KT
+   EXTENDED OUTLOOK:
+   120 HRS, VALID AT:
+   190000Z --- 13.1S 160.2W
+REMARKS:
+MINIMUM CENTRAL PRESSURE AT 140000Z IS
+918 MB.
+";
+
+    #[test]
+    fn reads_the_forecast_track_of_an_advisory() {
+        let track = parse_track(ADVISORY, ISSUED);
+        assert_eq!(track.len(), 2);
+        assert_eq!((track[0].time, track[0].lat, track[0].lon), (ISSUED + 9 * 3600, 35.0, -71.5));
+        assert_eq!(track[0].wind.map(|wind| (wind / KNOT).round()), Some(105.0));
+        assert_eq!((track[1].time, track[1].lat, track[1].lon), (ISSUED + 93 * 3600, 45.0, -55.0));
+        assert_eq!(track[1].wind.map(|wind| (wind / KNOT).round()), Some(50.0));
+    }
+
+    #[test]
+    fn advisory_times_resolve_into_the_next_month() {
+        // 2026-10-30T21:00:00Z, with a forecast on November 2
+        let issued = ISSUED + 16 * 86_400;
+        assert_eq!(valid_time("02/0600Z", issued).map(|time| time::date(time, 0)), Some("2026-11-02".to_string()));
+        assert_eq!(valid_time("30/2100Z", issued), Some(issued));
+        assert_eq!(valid_time("32/0000Z", issued), None);
+        assert_eq!(valid_time("15/06Z", issued), None);
+    }
+
+    #[test]
+    fn coordinates_are_signed_by_hemisphere() {
+        assert_eq!(coordinate("30.5N"), Some(30.5));
+        assert_eq!(coordinate("70.9W"), Some(-70.9));
+        assert_eq!(coordinate("13.1S"), Some(-13.1));
+        assert_eq!(coordinate("55.0W...POST-TROP/EXTRATROP"), Some(-55.0));
+        assert_eq!(coordinate("30.5"), None);
+        assert_eq!(coordinate(""), None);
+    }
+
+    #[test]
+    fn reads_a_jtwc_warning() {
+        let storm = Storm::from_jtwc(WARNING, ISSUED).unwrap();
+        assert_eq!((storm.name.as_str(), storm.kind()), ("Mawar", "Super Typhoon"));
+        assert_eq!((storm.lat, storm.lon, storm.pressure), (14.0, 143.9, Some(918.0)));
+        assert_eq!((storm.wind / KNOT).round(), 140.0);
+        assert_eq!(storm.heading, Some(290.0));
+        assert_eq!(storm.speed.map(|speed| (speed / KNOT).round()), Some(7.0));
+        // Issued at 03Z on the 14th
+        assert_eq!(storm.updated, Some(ISSUED - 18 * 3600));
+        assert_eq!(storm.track.len(), 2);
+        assert_eq!((storm.track[0].time, storm.track[0].lat, storm.track[0].lon), (ISSUED - 9 * 3600, 14.6, 142.9));
+        assert_eq!(storm.track[0].wind.map(|wind| (wind / KNOT).round()), Some(145.0));
+        assert_eq!((storm.track[1].lat, storm.track[1].lon, storm.track[1].wind), (-13.1, -160.2, None));
+    }
+
+    #[test]
+    fn jtwc_warnings_need_a_title_and_position() {
+        assert!(Storm::from_jtwc("WTPN31 PGTW 140300\nMAX SUSTAINED WINDS - 140 KT\n", ISSUED).is_none());
+        let unnamed = WARNING.replace("02W (MAWAR) WARNING", "03W WARNING").replace("SUPER TYPHOON", "TROPICAL DEPRESSION");
+        let storm = Storm::from_jtwc(&unnamed, ISSUED).unwrap();
+        assert_eq!((storm.name.as_str(), storm.kind()), ("03W", "Tropical Depression"));
+    }
+
+    #[test]
+    fn skips_feed_links_for_the_nhc_basins() {
+        let feed = "<a href='https://www.metoc.navy.mil/jtwc/products/wp0226web.txt'>TC Warning Text </a>\
+                    <a href='https://www.metoc.navy.mil/jtwc/products/wp0226prog.txt'>Prognostic Reasoning</a>\
+                    <a href='https://www.metoc.navy.mil/jtwc/products/ep1126web.txt'>TC Warning Text </a>\
+                    <a href='https://www.metoc.navy.mil/jtwc/products/sh0426web.txt'>TC Warning Text </a>\
+                    <link>https://www.metoc.navy.mil/jtwc/products/wp0226web.txt</link>";
+        assert_eq!(
+            warning_links(feed),
+            ["https://www.metoc.navy.mil/jtwc/products/wp0226web.txt", "https://www.metoc.navy.mil/jtwc/products/sh0426web.txt"]
+        );
+    }
+
+    #[test]
+    fn categories_follow_saffir_simpson() {
+        assert_eq!(category(63.0 * KNOT), None);
+        assert_eq!(category(64.0 * KNOT), Some(1));
+        assert_eq!(category(95.0 * KNOT), Some(2));
+        assert_eq!(category(137.0 * KNOT), Some(5));
+        assert_eq!(strength(34.0 * KNOT), "TS");
+        assert_eq!(strength(33.0 * KNOT), "TD");
+    }
+}
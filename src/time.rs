@@ -0,0 +1,167 @@
+use serde::{Deserialize, Serialize};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Clock style for displayed times
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum TimeFormat {
+    #[serde(rename = "12h")]
+    TwelveHour,
+    #[serde(rename = "24h")]
+    TwentyFourHour,
+}
+
+/// Formats a UNIX timestamp as a time of day at a location, given the
+/// location's offset from UTC in seconds, rather than the machine's timezone.
+pub fn format_time(timestamp: i64, utc_offset: i64, format: TimeFormat) -> String {
+    let seconds = (timestamp + utc_offset).rem_euclid(86_400);
+    let (hour, minute) = (seconds / 3600, seconds % 3600 / 60);
+
+    match format {
+        TimeFormat::TwentyFourHour => format!("{:02}:{:02}", hour, minute),
+        TimeFormat::TwelveHour => {
+            let suffix = if hour < 12 { "AM" } else { "PM" };
+            let hour = match hour % 12 {
+                0 => 12,
+                h => h,
+            };
+            format!("{}:{:02} {}", hour, minute, suffix)
+        }
+    }
+}
+
+/// Returns the UTC calendar month of a UNIX timestamp as "YYYY-MM"
+pub fn year_month(timestamp: i64) -> String {
+    let (year, month, _) = civil_date(timestamp);
+    format!("{:04}-{:02}", year, month)
+}
+
+/// Returns the calendar date of a UNIX timestamp as "YYYY-MM-DD", at the given
+/// offset from UTC in seconds
+pub fn date(timestamp: i64, utc_offset: i64) -> String {
+    let (year, month, day) = civil_date(timestamp + utc_offset);
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+/// Short weekday name of a UNIX timestamp at the given offset from UTC
+pub fn weekday(timestamp: i64, utc_offset: i64) -> &'static str {
+    const DAYS: [&str; 7] = ["Thu", "Fri", "Sat", "Sun", "Mon", "Tue", "Wed"];
+    // The UNIX epoch was a Thursday
+    DAYS[(timestamp + utc_offset).div_euclid(86_400).rem_euclid(7) as usize]
+}
+
+/// Formats a UNIX timestamp as an RFC 2822 date in UTC, as feeds and mail
+/// headers use, e.g. "Mon, 14 Oct 2024 05:33:00 +0000"
+pub fn rfc2822(timestamp: i64) -> String {
+    const MONTHS: [&str; 12] = ["Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec"];
+    let (year, month, day) = civil_date(timestamp);
+    let seconds = timestamp.rem_euclid(86_400);
+    format!(
+        "{}, {:02} {} {:04} {:02}:{:02}:{:02} +0000",
+        weekday(timestamp, 0),
+        day,
+        MONTHS[month as usize - 1],
+        year,
+        seconds / 3600,
+        seconds % 3600 / 60,
+        seconds % 60
+    )
+}
+
+/// Formats a UNIX timestamp as an ISO 8601 time in UTC, e.g.
+/// "2024-10-14T05:33:00Z", for APIs that take one
+pub fn iso_utc(timestamp: i64) -> String {
+    let (year, month, day) = civil_date(timestamp);
+    let seconds = timestamp.rem_euclid(86_400);
+    format!("{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z", year, month, day, seconds / 3600, seconds % 3600 / 60, seconds % 60)
+}
+
+/// Current UNIX timestamp
+pub fn now() -> i64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs() as i64).unwrap_or(0)
+}
+
+/// Describes a duration in seconds the way people say how old something is,
+/// e.g. "5 min", "2h" or "3 days"
+pub fn format_age(seconds: i64) -> String {
+    match seconds.max(0) {
+        s if s < 60 => "under a minute".to_string(),
+        s if s < 3600 => format!("{} min", s / 60),
+        s if s < 2 * 86_400 => format!("{}h", s / 3600),
+        s => format!("{} days", s / 86_400),
+    }
+}
+
+/// Reads an ISO 8601 time in UTC, e.g. "2024-10-14T05:33:00.000Z", as a
+/// UNIX timestamp, to the minute
+pub fn parse_utc(text: &str) -> Option<i64> {
+    let field = |range: std::ops::Range<usize>| text.get(range)?.parse::<i64>().ok();
+    let (year, month, day) = (field(0..4)?, field(5..7)?, field(8..10)?);
+    let (hour, minute) = (field(11..13)?, field(14..16)?);
+    Some(days_from_civil(year, month, day) * 86_400 + hour * 3600 + minute * 60)
+}
+
+/// Days since the UNIX epoch of a date, the inverse of `civil_date`
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * (month + if month > 2 { -3 } else { 9 }) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+/// Year, month and day of a UNIX timestamp in UTC
+fn civil_date(timestamp: i64) -> (i64, i64, i64) {
+    // Civil-from-days conversion for the proleptic Gregorian calendar
+    let days = timestamp.div_euclid(86_400) + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days - era * 146_097;
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let month = if month_index < 10 { month_index + 3 } else { month_index - 9 };
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn civil_dates_across_leap_days_and_the_epoch() {
+        assert_eq!(civil_date(0), (1970, 1, 1));
+        assert_eq!(civil_date(-1), (1969, 12, 31));
+        assert_eq!(civil_date(951_782_400), (2000, 2, 29));
+        assert_eq!(civil_date(1_709_164_800), (2024, 2, 29));
+        assert_eq!(civil_date(1_709_164_800 + 86_400), (2024, 3, 1));
+        // 1900 wasn't a leap year
+        assert_eq!(civil_date(-2_203_891_200 - 86_400), (1900, 2, 28));
+    }
+
+    #[test]
+    fn days_from_civil_inverts_civil_date() {
+        for days in (-800_000..800_000).step_by(97) {
+            let (year, month, day) = civil_date(days * 86_400);
+            assert_eq!(days_from_civil(year, month, day), days, "{}-{}-{}", year, month, day);
+        }
+    }
+
+    #[test]
+    fn parses_utc_times_to_the_minute() {
+        assert_eq!(parse_utc("2024-10-14T05:33:00.000Z"), Some(1_728_883_980));
+        assert_eq!(parse_utc("2024-10-14T05:33:59Z"), Some(1_728_883_980));
+        assert_eq!(parse_utc("2024-10-14 05:33"), Some(1_728_883_980));
+        assert_eq!(parse_utc("2024-10-14"), None);
+        assert_eq!(parse_utc("yesterday"), None);
+    }
+
+    #[test]
+    fn formats_iso_utc_and_rfc2822() {
+        assert_eq!(iso_utc(1_728_883_980), "2024-10-14T05:33:00Z");
+        assert_eq!(iso_utc(-1), "1969-12-31T23:59:59Z");
+        assert_eq!(rfc2822(1_728_883_980), "Mon, 14 Oct 2024 05:33:00 +0000");
+        assert_eq!(parse_utc(&iso_utc(1_709_164_800)), Some(1_709_164_800));
+    }
+}
@@ -0,0 +1,197 @@
+use serde::{Deserialize, Serialize};
+
+/// Unit presets accepted by `units` in the config and `--units`
+pub const PRESETS: [&str; 4] = ["imperial", "metric", "standard", "default"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TemperatureUnit {
+    Celsius,
+    Fahrenheit,
+    Kelvin,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SpeedUnit {
+    /// Meters per second
+    Mps,
+    /// Kilometers per hour
+    Kph,
+    /// Miles per hour
+    Mph,
+    Knots,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PressureUnit {
+    /// Hectopascals, the same as millibars
+    Hpa,
+    /// Inches of mercury
+    Inhg,
+    /// Millimeters of mercury
+    Mmhg,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PrecipitationUnit {
+    Mm,
+    In,
+}
+
+/// The unit chosen for each kind of measurement. Weather data is always
+/// fetched in metric and converted with these for display.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Units {
+    pub temperature: TemperatureUnit,
+    pub wind: SpeedUnit,
+    pub pressure: PressureUnit,
+    pub precipitation: PrecipitationUnit,
+}
+
+impl Units {
+    /// Units for a preset name, matching the OpenWeatherMap `units` values
+    pub fn preset(name: &str) -> Units {
+        match name {
+            "imperial" => Units {
+                temperature: TemperatureUnit::Fahrenheit,
+                wind: SpeedUnit::Mph,
+                pressure: PressureUnit::Inhg,
+                precipitation: PrecipitationUnit::In,
+            },
+            "metric" => Units {
+                temperature: TemperatureUnit::Celsius,
+                wind: SpeedUnit::Mps,
+                pressure: PressureUnit::Hpa,
+                precipitation: PrecipitationUnit::Mm,
+            },
+            _ => Units {
+                temperature: TemperatureUnit::Kelvin,
+                wind: SpeedUnit::Mps,
+                pressure: PressureUnit::Hpa,
+                precipitation: PrecipitationUnit::Mm,
+            },
+        }
+    }
+
+    pub fn is_imperial(&self) -> bool {
+        self.precipitation == PrecipitationUnit::In
+    }
+
+    /// Converts from degrees Celsius
+    pub fn temperature(&self, celsius: f64) -> f64 {
+        match self.temperature {
+            TemperatureUnit::Celsius => celsius,
+            TemperatureUnit::Fahrenheit => celsius * 9.0 / 5.0 + 32.0,
+            TemperatureUnit::Kelvin => celsius + 273.15,
+        }
+    }
+
+    /// Converts from meters per second
+    pub fn wind(&self, mps: f64) -> f64 {
+        match self.wind {
+            SpeedUnit::Mps => mps,
+            SpeedUnit::Kph => mps * 3.6,
+            SpeedUnit::Mph => mps * 2.236_936,
+            SpeedUnit::Knots => mps * 1.943_844,
+        }
+    }
+
+    /// Converts from hectopascals
+    pub fn pressure(&self, hpa: f64) -> f64 {
+        match self.pressure {
+            PressureUnit::Hpa => hpa,
+            PressureUnit::Inhg => hpa * 0.029_53,
+            PressureUnit::Mmhg => hpa * 0.750_062,
+        }
+    }
+
+    /// Converts from millimeters
+    pub fn precipitation(&self, mm: f64) -> f64 {
+        match self.precipitation {
+            PrecipitationUnit::Mm => mm,
+            PrecipitationUnit::In => mm / 25.4,
+        }
+    }
+
+    /// Converts from meters to kilometers, or to miles with imperial
+    /// precipitation units
+    pub fn distance(&self, meters: f64) -> f64 {
+        match self.is_imperial() {
+            true => meters / 1609.344,
+            false => meters / 1000.0,
+        }
+    }
+
+    pub fn temperature_symbol(&self) -> &'static str {
+        match self.temperature {
+            TemperatureUnit::Celsius => "°C",
+            TemperatureUnit::Fahrenheit => "°F",
+            TemperatureUnit::Kelvin => "K",
+        }
+    }
+
+    pub fn wind_symbol(&self) -> &'static str {
+        match self.wind {
+            SpeedUnit::Mps => "m/s",
+            SpeedUnit::Kph => "km/h",
+            SpeedUnit::Mph => "mph",
+            SpeedUnit::Knots => "kn",
+        }
+    }
+
+    pub fn pressure_symbol(&self) -> &'static str {
+        match self.pressure {
+            PressureUnit::Hpa => "hPa",
+            PressureUnit::Inhg => "inHg",
+            PressureUnit::Mmhg => "mmHg",
+        }
+    }
+
+    pub fn distance_symbol(&self) -> &'static str {
+        match self.is_imperial() {
+            true => "mi",
+            false => "km",
+        }
+    }
+
+    pub fn precipitation_symbol(&self) -> &'static str {
+        match self.precipitation {
+            PrecipitationUnit::Mm => "mm",
+            PrecipitationUnit::In => "in",
+        }
+    }
+
+    /// Formats a Celsius temperature with its unit
+    pub fn format_temperature(&self, celsius: f64) -> String {
+        format!("{:.1}{}", self.temperature(celsius), self.temperature_symbol())
+    }
+
+    /// Formats a wind speed in meters per second with its unit
+    pub fn format_wind(&self, mps: f64) -> String {
+        format!("{:.1} {}", self.wind(mps), self.wind_symbol())
+    }
+
+    /// Formats a pressure in hectopascals with its unit
+    pub fn format_pressure(&self, hpa: f64) -> String {
+        match self.pressure {
+            PressureUnit::Inhg => format!("{:.2} {}", self.pressure(hpa), self.pressure_symbol()),
+            _ => format!("{:.0} {}", self.pressure(hpa), self.pressure_symbol()),
+        }
+    }
+
+    /// Formats a distance in meters with its unit
+    pub fn format_distance(&self, meters: f64) -> String {
+        format!("{:.1} {}", self.distance(meters), self.distance_symbol())
+    }
+
+    /// Formats a precipitation amount in millimeters with its unit
+    pub fn format_precipitation(&self, mm: f64) -> String {
+        match self.precipitation {
+            PrecipitationUnit::In => format!("{:.2} {}", self.precipitation(mm), self.precipitation_symbol()),
+            PrecipitationUnit::Mm => format!("{:.1} {}", self.precipitation(mm), self.precipitation_symbol()),
+        }
+    }
+}
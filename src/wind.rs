@@ -0,0 +1,91 @@
+/// Gusts this much above the sustained speed, in m/s, are worth mentioning;
+/// about 10 knots, the threshold weather services report gusts at
+const GUST_MARGIN: f64 = 5.0;
+
+/// Below this speed, in m/s, the wind is calm and has no direction to speak of
+const CALM: f64 = 0.5;
+
+/// Upper bounds of the Beaufort forces in m/s, with their descriptions;
+/// anything above the last is hurricane force
+const BEAUFORT: [(f64, &str); 12] = [
+    (0.5, "calm"),
+    (1.6, "light air"),
+    (3.4, "light breeze"),
+    (5.5, "gentle breeze"),
+    (8.0, "moderate breeze"),
+    (10.8, "fresh breeze"),
+    (13.9, "strong breeze"),
+    (17.2, "near gale"),
+    (20.8, "gale"),
+    (24.5, "strong gale"),
+    (28.5, "storm"),
+    (32.7, "violent storm"),
+];
+
+const POINTS: [&str; 16] = [
+    "N", "NNE", "NE", "ENE", "E", "ESE", "SE", "SSE", "S", "SSW", "SW", "WSW", "W", "WNW", "NW", "NNW",
+];
+
+/// Arrows pointing the way the wind blows, for winds from N, NE, E, ...
+const ARROWS: [char; 8] = ['↓', '↙', '←', '↖', '↑', '↗', '→', '↘'];
+
+/// 16-point compass abbreviation of the direction the wind comes from
+pub fn compass(deg: f64) -> &'static str {
+    POINTS[((deg.rem_euclid(360.0) / 22.5).round() as usize) % 16]
+}
+
+/// Arrow showing which way a wind from `deg` blows, e.g. '↘' for a
+/// northwesterly
+pub fn arrow(deg: f64) -> char {
+    ARROWS[((deg.rem_euclid(360.0) / 45.0).round() as usize) % 8]
+}
+
+/// Beaufort force and description of a speed in m/s
+pub fn beaufort(mps: f64) -> (u8, &'static str) {
+    BEAUFORT
+        .iter()
+        .position(|(limit, _)| mps < *limit)
+        .map_or((12, "hurricane force"), |force| (force as u8, BEAUFORT[force].1))
+}
+
+/// Whether gusts stand out enough from the sustained speed to show
+pub fn is_gusty(speed: f64, gust: f64) -> bool {
+    gust - speed >= GUST_MARGIN
+}
+
+/// Whether the wind is too light to have a meaningful direction
+pub fn is_calm(speed: f64) -> bool {
+    speed < CALM
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn beaufort_bounds_belong_to_the_next_force() {
+        assert_eq!(beaufort(0.0), (0, "calm"));
+        assert_eq!(beaufort(0.49), (0, "calm"));
+        assert_eq!(beaufort(0.5), (1, "light air"));
+        assert_eq!(beaufort(10.79), (5, "fresh breeze"));
+        assert_eq!(beaufort(10.8), (6, "strong breeze"));
+        assert_eq!(beaufort(32.69), (11, "violent storm"));
+        assert_eq!(beaufort(32.7), (12, "hurricane force"));
+        assert_eq!(beaufort(80.0), (12, "hurricane force"));
+    }
+
+    #[test]
+    fn beaufort_forces_rise_with_speed() {
+        let forces: Vec<u8> = (0..400).map(|tenths| beaufort(tenths as f64 / 10.0).0).collect();
+        assert!(forces.windows(2).all(|pair| pair[0] <= pair[1]));
+        assert_eq!(forces.last(), Some(&12));
+    }
+
+    #[test]
+    fn compass_points_wrap_around() {
+        assert_eq!(compass(0.0), "N");
+        assert_eq!(compass(359.0), "N");
+        assert_eq!(compass(-90.0), "W");
+        assert_eq!(compass(292.5), "WNW");
+    }
+}
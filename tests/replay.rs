@@ -0,0 +1,334 @@
+//! Runs the binary against recorded API responses in `tests/fixtures`, so
+//! the parsing and formatting are checked without a network or an API key.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const CONFIG: &str = "api_key: test\nlatitude: 42.81\nlongitude: -73.94\nunits: metric\n";
+
+/// A config file and empty data directories, removed when dropped
+struct Sandbox {
+    dir: PathBuf,
+}
+
+impl Sandbox {
+    fn new(name: &str, config: &str) -> Sandbox {
+        let dir = std::env::temp_dir().join(format!("rusty_weather-test-{}-{}", std::process::id(), name));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("config.yaml"), config).unwrap();
+        Sandbox { dir }
+    }
+
+    /// Runs the binary with `args`, answering requests from the fixture
+    fn run(&self, fixture: &str, args: &[&str]) -> Output {
+        let mut command = Command::new(env!("CARGO_BIN_EXE_RustyWeather"));
+        command
+            .arg("--config")
+            .arg(self.dir.join("config.yaml"))
+            .arg("--replay")
+            .arg(Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures").join(fixture))
+            .args(args)
+            .current_dir(&self.dir)
+            .env("HOME", &self.dir);
+        for var in ["XDG_CONFIG_HOME", "XDG_DATA_HOME", "XDG_CACHE_HOME", "XDG_STATE_HOME"] {
+            command.env(var, self.dir.join(var));
+        }
+        for (var, _) in std::env::vars().filter(|(var, _)| var.starts_with("RUSTY_WEATHER_")) {
+            command.env_remove(var);
+        }
+        command.output().unwrap()
+    }
+}
+
+impl Drop for Sandbox {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.dir);
+    }
+}
+
+/// ISO 8601 time in UTC of a UNIX timestamp, e.g. "2024-10-14T05:33:00Z"
+fn iso_utc(timestamp: i64) -> String {
+    // Civil-from-days conversion for the proleptic Gregorian calendar
+    let days = timestamp.div_euclid(86_400) + 719_468;
+    let (era, day_of_era) = (days.div_euclid(146_097), days.rem_euclid(146_097));
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let month = if month_index < 10 { month_index + 3 } else { month_index - 9 };
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    let seconds = timestamp.rem_euclid(86_400);
+    format!("{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z", year, month, day, seconds / 3600, seconds % 3600 / 60, seconds % 60)
+}
+
+fn now() -> i64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs() as i64
+}
+
+/// An FMI lightning response, one element per parameter of each
+/// (time, latitude, longitude, cloud indicator) strike
+fn fmi_strikes(strikes: &[(i64, f64, f64, u8)]) -> String {
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<wfs:FeatureCollection>\n");
+    for (time, lat, lon, cloud) in strikes {
+        for (name, value) in [("multiplicity", "1".to_string()), ("peak_current", "-12.0".to_string()), ("cloud_indicator", cloud.to_string())] {
+            xml.push_str(&format!(
+                "<wfs:member><BsWfs:BsWfsElement gml:id=\"e\"><BsWfs:Location><gml:Point gml:id=\"p\" srsDimension=\"2\">\
+                 <gml:pos>{:.5} {:.5} </gml:pos></gml:Point></BsWfs:Location><BsWfs:Time>{}</BsWfs:Time>\
+                 <BsWfs:ParameterName>{}</BsWfs:ParameterName><BsWfs:ParameterValue>{}</BsWfs:ParameterValue>\
+                 </BsWfs:BsWfsElement></wfs:member>\n",
+                lat,
+                lon,
+                iso_utc(*time),
+                name,
+                value
+            ));
+        }
+    }
+    xml + "</wfs:FeatureCollection>\n"
+}
+
+/// Day, hour and minute of a UNIX timestamp as JTWC warnings give them, e.g. "240300"
+fn day_time(timestamp: i64) -> String {
+    let iso = iso_utc(timestamp);
+    format!("{}{}{}", &iso[8..10], &iso[11..13], &iso[14..16])
+}
+
+/// A JTWC text warning with the storm's position at `day`, a midnight UTC,
+/// and forecasts 12 and 24 hours on
+fn jtwc_warning(day: i64) -> String {
+    format!(
+        "WTPN31 PGTW {issued}\n\
+         MSGID/GENADMIN/JOINT TYPHOON WRNCEN PEARL HARBOR HI//\n\
+         SUBJ/TROPICAL CYCLONE WARNING//\n\
+         RMKS/\n\
+         1. SUPER TYPHOON 02W (MAWAR) WARNING NR 030\n\
+         MAX SUSTAINED WINDS BASED ON ONE-MINUTE AVERAGE\n\
+         ---\n\
+         WARNING POSITION:\n\
+         {now}Z --- NEAR 14.0N 143.9E\n\
+         MOVEMENT PAST SIX HOURS - 290 DEGREES AT 07 KTS\n\
+         PRESENT WIND DISTRIBUTION:\n\
+         MAX SUSTAINED WINDS - 140 KT, GUSTS 170 KT\n\
+         ---\n\
+         FORECASTS:\n\
+         12 HRS, VALID AT:\n\
+         {twelve}Z --- 14.6N 142.9E\n\
+         MAX SUSTAINED WINDS - 145 KT, GUSTS 175 KT\n\
+         ---\n\
+         24 HRS, VALID AT:\n\
+         {day}Z --- 15.1N 141.6E\n\
+         MAX SUSTAINED WINDS - 140 KT, GUSTS 170 KT\n\
+         REMARKS:\n\
+         SUPER TYPHOON 02W (MAWAR), LOCATED APPROXIMATELY 77 NM NORTHEAST OF\n\
+         ANDERSEN AFB. MINIMUM CENTRAL PRESSURE AT {now}Z IS\n\
+         918 MB.\n",
+        issued = day_time(day + 3 * 3600),
+        now = day_time(day),
+        twelve = day_time(day + 12 * 3600),
+        day = day_time(day + 24 * 3600),
+    )
+}
+
+fn stdout(output: &Output) -> String {
+    assert!(output.status.success(), "failed: {}", String::from_utf8_lossy(&output.stderr));
+    String::from_utf8(output.stdout.clone()).unwrap()
+}
+
+#[test]
+fn current_weather_from_openweathermap() {
+    let sandbox = Sandbox::new("current", CONFIG);
+    let out = stdout(&sandbox.run("current.json", &["--plain"]));
+    for line in [
+        "Temperature: -7.1°C",
+        "Feels Like: -12.5°C",
+        "Wind Speed: 7.7 m/s",
+        "Humidity: 71%",
+        "Pressure: 1020 hPa",
+        "Conditions: overcast clouds",
+        "Sunrise: 06:58",
+    ] {
+        assert!(out.contains(line), "missing {:?} in:\n{}", line, out);
+    }
+}
+
+#[test]
+fn current_weather_in_imperial_units() {
+    let sandbox = Sandbox::new("imperial", CONFIG);
+    let out = stdout(&sandbox.run("current.json", &["--units", "imperial", "--get", "temp"]));
+    assert_eq!(out.trim(), "19.29");
+}
+
+#[test]
+fn current_weather_from_open_meteo() {
+    let sandbox = Sandbox::new("open-meteo", &format!("{}provider: open-meteo\n", CONFIG));
+    let out = stdout(&sandbox.run("open_meteo.json", &["--plain"]));
+    for line in ["Temperature: -6.5°C", "Feels Like: -12.0°C", "Humidity: 80%", "Min: -9.0°C", "Max: -4.0°C"] {
+        assert!(out.contains(line), "missing {:?} in:\n{}", line, out);
+    }
+}
+
+#[test]
+fn forecast_groups_days() {
+    let sandbox = Sandbox::new("forecast", CONFIG);
+    let out = stdout(&sandbox.run("forecast.json", &["forecast"]));
+    assert!(out.starts_with("Forecast for Schenectady:"), "{}", out);
+    assert!(out.contains("Tue 2023-11-14 |   -5.0°C /   -4.0°C |  40%   1.2 mm | light snow"), "{}", out);
+    assert_eq!(out.lines().filter(|line| line.contains(" | ")).count(), 5, "{}", out);
+
+    let out = stdout(&sandbox.run("forecast.json", &["forecast", "--days", "2"]));
+    assert_eq!(out.lines().filter(|line| line.contains(" | ")).count(), 2, "{}", out);
+}
+
+#[test]
+fn alerts_in_local_time() {
+    let sandbox = Sandbox::new("alerts", CONFIG);
+    let out = stdout(&sandbox.run("alerts.json", &["alerts"]));
+    assert!(out.contains("Winter Storm Warning (Tue 20:00 - Wed 21:00)"), "{}", out);
+    assert!(out.contains("Issued by NWS Albany"), "{}", out);
+    assert!(out.contains("Total accumulations 8 to 12 inches."), "{}", out);
+}
+
+#[test]
+fn raw_prints_the_response() {
+    let sandbox = Sandbox::new("raw", CONFIG);
+    let out = stdout(&sandbox.run("alerts.json", &["alerts", "--raw"]));
+    let json: serde_json::Value = serde_json::from_str(&out).unwrap();
+    assert_eq!(json["alerts"][0]["sender_name"], "NWS Albany");
+}
+
+#[test]
+fn rejected_key_exits_with_auth_code() {
+    let sandbox = Sandbox::new("unauthorized", CONFIG);
+    let output = sandbox.run("unauthorized.json", &[]);
+    assert_eq!(output.status.code(), Some(5), "{}", String::from_utf8_lossy(&output.stderr));
+}
+
+#[test]
+fn server_error_says_to_try_later() {
+    let sandbox = Sandbox::new("server-error", CONFIG);
+    let output = sandbox.run("server_error.json", &[]);
+    assert_eq!(output.status.code(), Some(9));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("The service returned HTTP 503. Try again later."));
+}
+
+#[test]
+fn fire_counts_nearby_lightning() {
+    let sandbox = Sandbox::new("fire", "api_key: test\nlatitude: 61.5\nlongitude: 23.8\nunits: metric\n");
+    let fixture = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/fire.json");
+    let mut exchanges: Vec<serde_json::Value> = serde_json::from_str(&fs::read_to_string(fixture).unwrap()).unwrap();
+
+    // Two strikes to ground and one in the clouds nearby, one too far and one too old
+    let now = now();
+    let body = fmi_strikes(&[
+        (now - 7200, 61.55, 23.9, 0),
+        (now - 3600, 61.6, 23.7, 1),
+        (now - 600, 61.52, 23.82, 0),
+        (now - 1200, 62.5, 23.8, 0),
+        (now - 30 * 3600, 61.5, 23.8, 0),
+    ]);
+    // The request starts on the hour a day back; the next hour is there in case it turns mid-test
+    for hour in [now - now % 3600, now - now % 3600 + 3600] {
+        let url = format!(
+            "https://opendata.fmi.fi/wfs?service=WFS&version=2.0.0&request=getFeature\
+             &storedquery_id=fmi%3A%3Aobservations%3A%3Alightning%3A%3Asimple&bbox=22.86%2C61.05%2C24.74%2C61.95&starttime={}",
+            iso_utc(hour - 24 * 3600).replace(':', "%3A")
+        );
+        exchanges.push(serde_json::json!({ "url": url, "status": 200, "body": body }));
+    }
+    let path = sandbox.dir.join("fire.json");
+    fs::write(&path, serde_json::to_string(&exchanges).unwrap()).unwrap();
+
+    let out = stdout(&sandbox.run(path.to_str().unwrap(), &["fire", "--plain"]));
+    assert!(out.contains("Lightning last 24h: 3 within 50 km (2 to ground), nearest 2 km, latest 10 min ago"), "{}", out);
+    assert!(out.contains("2 lightning strike(s) to ground within 50 km in the last 24 hours on dry fuels"), "{}", out);
+}
+
+#[test]
+fn fire_outside_the_lightning_network() {
+    let sandbox = Sandbox::new("fire-uncovered", "api_key: test\nlatitude: 1\nlongitude: 2\nunits: metric\n");
+    let out = stdout(&sandbox.run("fire_uncovered.json", &["fire", "--plain"]));
+    assert!(out.contains("Lightning last 24h: no strike data here"), "{}", out);
+    assert!(out.contains("Critical fire weather now: humidity 14% with wind 8.0 m/s, gusts 12.8 m/s"), "{}", out);
+}
+
+#[test]
+fn storms_from_both_warning_centers() {
+    let sandbox = Sandbox::new("storms", "api_key: test\nlatitude: 13.44\nlongitude: 144.79\nunits: metric\n");
+    let fixture = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/storms.json");
+    let mut exchanges: Vec<serde_json::Value> = serde_json::from_str(&fs::read_to_string(fixture).unwrap()).unwrap();
+    let feed = "<rss version=\"2.0\"><channel><item><description><![CDATA[\
+                <a href='https://www.metoc.navy.mil/jtwc/products/wp0226web.txt'>TC Warning Text </a>\
+                <a href='https://www.metoc.navy.mil/jtwc/products/wp0226prog.txt'>Prognostic Reasoning</a>\
+                <a href='https://www.metoc.navy.mil/jtwc/products/ep1126web.txt'>TC Warning Text </a>\
+                ]]></description></item></channel></rss>";
+    exchanges.push(serde_json::json!({ "url": "https://www.metoc.navy.mil/jtwc/rss/jtwc.rss", "status": 200, "body": feed }));
+    // Issued yesterday, so the day of the month resolves to the right date
+    let yesterday = now() - now() % 86_400 - 86_400;
+    let warning = jtwc_warning(yesterday);
+    exchanges.push(serde_json::json!({ "url": "https://www.metoc.navy.mil/jtwc/products/wp0226web.txt", "status": 200, "body": warning }));
+    let path = sandbox.dir.join("storms.json");
+    fs::write(&path, serde_json::to_string(&exchanges).unwrap()).unwrap();
+
+    let out = stdout(&sandbox.run(path.to_str().unwrap(), &["storms", "--plain"]));
+    assert!(out.starts_with("1. Super Typhoon Mawar\n"), "{}", out);
+    assert!(out.contains("Wind: 72.0 m/s, pressure 918 hPa"), "{}", out);
+    assert!(out.contains("Position: 14.0N 143.9E, 115 km WNW of you"), "{}", out);
+    assert!(out.contains("Moving: WNW at 3.6 m/s"), "{}", out);
+    assert!(out.contains(", 15.1N 141.6E, 390 km, cat 5, 72.0 m/s"), "{}", out);
+    assert!(out.contains("2 more active elsewhere, see --all."), "{}", out);
+
+    let out = stdout(&sandbox.run(path.to_str().unwrap(), &["storms", "--all", "--plain"]));
+    assert!(out.contains("3. Hurricane Franklin, category 4"), "{}", out);
+    assert!(out.contains("Forecast: Thu 18:00 UTC, 37.5N 71.0W, 13229 km, cat 2, 46.3 m/s"), "{}", out);
+}
+
+#[test]
+fn storms_say_when_your_basin_is_missing() {
+    let sandbox = Sandbox::new("storms-missing", "api_key: test\nlatitude: 13.44\nlongitude: 144.79\nunits: metric\n");
+    let out = stdout(&sandbox.run("storms.json", &["storms", "--plain"]));
+    assert!(out.contains("No active tropical cyclones within 1500 km; 2 elsewhere, see --all."), "{}", out);
+    assert!(
+        out.contains("Storms in the western Pacific, the Indian Ocean and the southern hemisphere, where you are, aren't listed"),
+        "{}",
+        out
+    );
+}
+
+#[test]
+fn snow_report_sums_fresh_snow() {
+    let sandbox = Sandbox::new("snow", CONFIG);
+    let out = stdout(&sandbox.run("snow.json", &["snow"]));
+    for line in ["Snow Depth: 32 cm", "Fresh Snow (24h): 5.0 cm", "Fresh Snow (48h): 7.0 cm", "Freezing Level: 850 m"] {
+        assert!(out.contains(line), "missing {:?} in:\n{}", line, out);
+    }
+    // The forecast starts today, the local date of the current time
+    assert!(out.contains("Snow forecast:\n2023-11-14 |   8.2 cm ********\n"), "{}", out);
+    assert_eq!(out.lines().filter(|line| line.starts_with("2023-")).count(), 7, "{}", out);
+
+    let out = stdout(&sandbox.run("snow.json", &["--units", "imperial", "snow"]));
+    assert!(out.contains("Snow Depth: 13 in") && out.contains("Freezing Level: 2789 ft"), "{}", out);
+}
+
+#[test]
+fn prompt_shows_what_the_refresh_saved() {
+    let sandbox = Sandbox::new("prompt", CONFIG);
+    // Nothing is saved yet, so the first render would only start a refresh; run it directly
+    let refresh = sandbox.run("current.json", &["prompt", "--refresh"]);
+    assert!(refresh.status.success(), "{}", String::from_utf8_lossy(&refresh.stderr));
+    assert_eq!(stdout(&sandbox.run("current.json", &["prompt"])), "☁ -7°C\n");
+    assert_eq!(stdout(&sandbox.run("current.json", &["prompt", "--plain"])), "Clouds -7°C\n");
+    assert_eq!(stdout(&sandbox.run("current.json", &["prompt", "--color", "ansi"])), "☁ \u{1b}[36m-7°C\u{1b}[0m\n");
+}
+
+#[test]
+fn prompt_refuses_lookups() {
+    let sandbox = Sandbox::new("prompt-lookup", &format!("{}provider: open-meteo\n", CONFIG));
+    for args in [&["--city", "Albany", "prompt"][..], &["--zip", "12305", "prompt"]] {
+        let output = sandbox.run("current.json", args);
+        assert_eq!(output.status.code(), Some(2), "{:?}", args);
+        assert!(String::from_utf8_lossy(&output.stderr).contains("prompt runs on every shell prompt"), "{:?}", args);
+    }
+}